@@ -0,0 +1,116 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Pages served by Sydney itself under the `about:` scheme
+
+use std::fmt::Write;
+
+/// Generate the Gemtext of the about page at `path`, e.g., `help` for
+/// `about:help`
+pub fn page(config: &crate::config::Config, path: &str) -> Option<String> {
+  match path {
+    "blank" => Some(String::new()),
+    "help" => Some(help()),
+    "keys" => Some(keys()),
+    "config" => Some(self::config(config)),
+    "version" => Some(version()),
+    "sent" => Some(crate::misfin::sent()),
+    _ => None,
+  }
+}
+
+fn help() -> String {
+  let mut page = format!(
+    "# Sydney Help\n\n{}.\n\nMove around pages with the keyboard, and run \
+     commands by pressing \":\" and typing one of the commands below.\n\n=> \
+     about:keys Key bindings\n=> about:config Configuration\n=> about:version \
+     Version\n=> about:sent Sent messages\n\n## Commands\n",
+    env!("CARGO_PKG_DESCRIPTION")
+  );
+
+  for command in crate::command::COMMANDS {
+    let _ = write!(page, "\n### :{}\n\n{}.\n", command.usage(), command.help);
+
+    if !command.aliases.is_empty() {
+      let _ = writeln!(
+        page,
+        "\n* Aliases: {}",
+        command
+          .aliases
+          .iter()
+          .map(|alias| format!(":{alias}"))
+          .collect::<Vec<_>>()
+          .join(", ")
+      );
+    }
+  }
+
+  page
+}
+
+fn keys() -> String {
+  let mut page = "# Key Bindings\n".to_string();
+  let mut mode = "";
+
+  for binding in crate::input::BINDINGS {
+    if binding.mode != mode {
+      mode = binding.mode;
+
+      let _ = writeln!(page, "\n## {mode}\n");
+    }
+
+    let _ = writeln!(page, "* {}: {}", binding.keys, binding.description);
+  }
+
+  page
+}
+
+fn config(config: &crate::config::Config) -> String {
+  let mut page = "# Configuration\n\n".to_string();
+
+  if let Some(path) = crate::config::path() {
+    let _ = writeln!(page, "Sydney reads its configuration from:\n");
+    let _ = writeln!(page, "```\n{}\n```\n", path.display());
+  }
+
+  let _ = writeln!(page, "## Current Configuration\n\n```");
+
+  for (key, value) in config.entries() {
+    let _ = writeln!(page, "{key} = {value}");
+  }
+
+  if !config.identities.is_empty() {
+    let _ = writeln!(page, "\n[identities]");
+
+    for (host, identity) in &config.identities {
+      let _ = writeln!(page, "{host} = {identity}");
+    }
+  }
+
+  page.push_str("```\n");
+
+  page
+}
+
+fn version() -> String {
+  format!(
+    "# Sydney {}\n\n=> {} Source code\n=> {}/issues Report a bug\n",
+    env!("CARGO_PKG_VERSION"),
+    env!("CARGO_PKG_REPOSITORY"),
+    env!("CARGO_PKG_REPOSITORY"),
+  )
+}
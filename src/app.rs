@@ -15,191 +15,387 @@
 // Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+//! The state machine behind the interface
+
 use std::time::{Duration, Instant};
 
 use crossterm::event;
-use germ::{ast::Node, request::Status};
+use ratatui::layout::Rect;
 use url::Url;
 
-use crate::{input::Mode as InputMode, stateful_list::StatefulList};
+use crate::{
+  config::{Config, Startup},
+  document::Item,
+  input::Mode as InputMode,
+  line_editor::LineEditor,
+  stateful_list::StatefulList,
+  tab::Tab,
+  transport::Registry,
+  watch::Watch,
+};
 
+/// The state of the browser: the displayed page, its tabs, history and
+/// prompts
+///
+/// An `App` is driven by feeding terminal events to [`crate::input`] and drawn
+/// with [`crate::ui::ui`], which [`App::run`] does for a real terminal.
 pub struct App {
-  pub items:                  StatefulList<(Vec<Node>, Option<String>, bool)>,
-  pub input:                  String,
-  pub input_mode:             InputMode,
-  pub command_stroke_history: Vec<event::KeyCode>,
-  pub command_history:        Vec<String>,
-  pub command_history_cursor: usize,
-  pub error:                  Option<String>,
-  pub url:                    Url,
-  pub capsule_history:        Vec<Url>,
-  pub previous_capsule:       Option<Url>,
-  pub response_input:         String,
-  pub accept_response_input:  bool,
-  pub response_input_text:    String,
-  pub wrap_at:                u16,
+  pub(crate) items:                  StatefulList<Item>,
+  pub(crate) input:                  LineEditor,
+  pub(crate) input_mode:             InputMode,
+  pub(crate) command_stroke_history: Vec<event::KeyCode>,
+  pub(crate) command_history:        Vec<String>,
+  pub(crate) error:                  Option<String>,
+  pub(crate) url:                    Url,
+  pub(crate) capsule_history:        Vec<Url>,
+  pub(crate) previous_capsule:       Option<Url>,
+  pub(crate) response_input:         String,
+  pub(crate) accept_response_input:  bool,
+  pub(crate) response_input_text:    String,
+  /// The URL a Spartan prompt line asked for input to upload to, which the
+  /// response input is sent to instead of the current page
+  pub(crate) upload:                 Option<Url>,
+  pub(crate) wrap_at:                u16,
+  pub(crate) show_toc:               bool,
+  pub(crate) config:                 Config,
+  pub(crate) source:                 String,
+  pub(crate) message:                Option<String>,
+  pub(crate) visual_anchor:          usize,
+  pub(crate) tabs:                   Vec<Tab>,
+  pub(crate) current_tab:            usize,
+  pub(crate) list_area:              Rect,
+  pub(crate) item_heights:           Vec<usize>,
+  pub(crate) notification:           Option<String>,
+  pub(crate) update_check:           Option<std::sync::mpsc::Receiver<String>>,
+  /// The document read from standard input, displayed as `about:stdin`
+  pub(crate) stdin:                  Option<String>,
+  /// The URL links of the standard input document are relative to
+  pub(crate) stdin_base:             Option<Url>,
+  pub(crate) watch:                  Option<Watch>,
+  /// Which items changed when the current page was last reloaded
+  pub(crate) changed:                Vec<bool>,
+  /// Whether the terminal has to be redrawn from scratch, e.g., after an
+  /// external editor used it
+  pub(crate) clear:                  bool,
+  pub(crate) transports:             Registry,
 }
 impl App {
-  pub fn new() -> Self {
-    let url = Url::parse("gemini://fuwn.me/blog/technology/gemini?referrer=sydney").unwrap();
+  /// A browser at `about:blank` with a single tab, call [`App::start`] or
+  /// [`App::restore`] to open a page
+  ///
+  /// # Panics
+  ///
+  /// Never, `about:blank` is always a valid URL.
+  #[must_use]
+  pub fn new(config: Config) -> Self {
+    let url = Url::parse("about:blank").unwrap();
 
-    let mut app = Self {
+    Self {
       response_input: String::new(),
       error: None,
       command_stroke_history: Vec::new(),
-      input: String::new(),
+      input: LineEditor::default(),
       input_mode: InputMode::Normal,
       items: StatefulList::with_items(Vec::new()),
       command_history: vec![],
-      command_history_cursor: 0,
-      url,
+      url: url.clone(),
       capsule_history: vec![],
       previous_capsule: None,
       accept_response_input: false,
-      response_input_text: "".to_string(),
+      response_input_text: String::new(),
+      upload: None,
       wrap_at: crossterm::terminal::size().unwrap_or((80, 24)).0,
-    };
+      show_toc: false,
+      config,
+      source: String::new(),
+      message: None,
+      visual_anchor: 0,
+      tabs: vec![Tab::new(url)],
+      current_tab: 0,
+      list_area: Rect::default(),
+      item_heights: Vec::new(),
+      notification: None,
+      update_check: None,
+      stdin: None,
+      stdin_base: None,
+      watch: None,
+      changed: Vec::new(),
+      clear: false,
+      transports: Registry::default(),
+    }
+  }
+
+  /// The URL of the current page
+  #[must_use]
+  pub const fn url(&self) -> &Url { &self.url }
+
+  /// The source of the current page, as it was received
+  #[must_use]
+  pub fn source(&self) -> &str { &self.source }
+
+  /// The lines of the current page
+  #[must_use]
+  pub fn items(&self) -> &[Item] { &self.items.items }
+
+  /// The pages visited in the current tab, oldest first
+  #[must_use]
+  pub fn history(&self) -> &[Url] { &self.capsule_history }
+
+  /// The error shown over the page, if any
+  #[must_use]
+  pub fn error(&self) -> Option<&str> { self.error.as_deref() }
+
+  /// The message shown in the status bar, if any
+  #[must_use]
+  pub fn message(&self) -> Option<&str> { self.message.as_deref() }
+
+  /// Whether the current page asked for input, which is being typed
+  #[must_use]
+  pub const fn accepts_input(&self) -> bool { self.accept_response_input }
+
+  /// The configuration the browser was created with
+  #[must_use]
+  pub const fn config(&self) -> &Config { &self.config }
+
+  /// Show `error` over the page
+  pub fn set_error(&mut self, error: String) { self.error = Some(error); }
+
+  /// Wrap documents at `wrap_at` columns instead of the terminal width
+  pub const fn set_wrap_at(&mut self, wrap_at: u16) { self.wrap_at = wrap_at; }
+
+  /// Display `document` as `about:stdin`, resolving its links against `base`
+  pub fn set_stdin(&mut self, document: String, base: Option<Url>) {
+    self.stdin = Some(document);
+    self.stdin_base = base;
+  }
+
+  /// Fetch pages through `transports` instead of the network
+  pub fn set_transports(&mut self, transports: Registry) {
+    self.transports = transports;
+  }
+
+  /// Check for a newer version of Sydney in the background, if configured to
+  pub fn check_for_updates(&mut self) {
+    if self.config.update_check {
+      self.update_check = Some(crate::update::spawn());
+    }
+  }
 
-    app.make_request();
+  /// Show the configured homepage, or restore the previous session if
+  /// configured to and one was saved
+  pub fn start(&mut self) {
+    if self.config.startup == Startup::Session {
+      if let Some((current, urls)) = crate::session::load() {
+        self.restore(current, urls);
 
-    app
+        return;
+      }
+    }
+
+    match crate::url::parse_location(&self.config.homepage) {
+      Ok(url) => {
+        self.url = url;
+
+        self.make_request();
+      }
+      Err(error) =>
+        self.error = Some(format!("Could not open the homepage: {error}")),
+    }
   }
 
+  /// Open `urls` as tabs, displaying the one at `current`
+  pub fn restore(&mut self, current: usize, urls: Vec<Url>) {
+    let current = current.min(urls.len().saturating_sub(1));
+
+    self.tabs = urls.into_iter().map(Tab::new).collect();
+
+    if self.tabs.is_empty() {
+      self.tabs.push(Tab::new(self.url.clone()));
+
+      return;
+    }
+
+    self.current_tab = current;
+    self.url = self.tabs[current].url.clone();
+    self.tabs[current].loaded = true;
+
+    self.make_request();
+  }
+
+  /// The URL of every tab, in order
+  #[must_use]
+  pub fn tab_urls(&self) -> Vec<&Url> {
+    self
+      .tabs
+      .iter()
+      .enumerate()
+      .map(|(index, tab)| {
+        if index == self.current_tab {
+          &self.url
+        } else {
+          &tab.url
+        }
+      })
+      .collect()
+  }
+
+  /// Move to `url` without requesting it, remembering the current URL
   pub fn set_url(&mut self, url: Url) {
     self.previous_capsule = Some(self.url.clone());
     self.url = url;
   }
 
+  /// The URL links on the current page are relative to
+  #[must_use]
+  pub fn base(&self) -> &Url {
+    match &self.stdin_base {
+      Some(base) if self.url.as_str() == crate::stdin::URL => base,
+      _ => &self.url,
+    }
+  }
+
+  /// Request the current URL, replacing the displayed page with the response
+  /// or setting `error` if it could not be fetched
   pub fn make_request(&mut self) {
-    self.items = StatefulList::with_items({
-      let mut items: Vec<(Vec<Node>, Option<String>, bool)> = vec![];
-
-      match germ::request::request(&self.url) {
-        Ok(mut response) => {
-          if response.status() == &Status::TemporaryRedirect
-            || response.status() == &Status::PermanentRedirect
-          {
-            self.url = Url::parse(&if response.meta().starts_with('/') {
-              format!(
-                "gemini://{}{}",
-                self.url.host_str().unwrap(),
-                response.meta()
-              )
-            } else if response.meta().starts_with("gemini://") {
-              response.meta().to_string()
-            } else if !response.meta().starts_with('/')
-              && !response.meta().starts_with("gemini://")
-            {
-              format!(
-                "{}/{}",
-                self.url.to_string().trim_end_matches('/'),
-                response.meta()
-              )
-            } else {
-              self.url.to_string()
-            })
-            .unwrap();
-
-            response = germ::request::request(&self.url).unwrap();
-          }
+    if self.url.as_str() == crate::stdin::URL {
+      if let Some(stdin) = self.stdin.clone() {
+        self.load_gemtext(&stdin);
+        self.record_visit();
 
-          if response.status() == &Status::Input
-            || response.status() == &Status::SensitiveInput
-          {
-            self.accept_response_input = true;
-            self.response_input_text = response.meta().to_string();
-            items = self.items.items.clone();
-          }
+        return;
+      }
+    }
 
-          // items.push((
-          //   vec![Node::Text(response.meta().to_string())],
-          //   None,
-          //   false,
-          // ));
-          // items.push((vec![Node::Text("".to_string())], None, false));
-
-          let mut pre = false;
-
-          if let Some(content) = response.content().clone() {
-            let real_lines = content.lines();
-
-            for line in real_lines {
-              let line = line.replace('\t', " ");
-              let pre_like = if line.starts_with("```") {
-                pre = !pre;
-
-                true
-              } else {
-                false
-              };
-
-              let ast = germ::ast::Ast::from_string(&line);
-              let ast_node = ast.inner().first().map_or_else(
-                || {
-                  if pre_like || pre {
-                    if line == "```" {
-                      Node::Text("sydney_abc_123".to_string())
-                    } else {
-                      Node::Text(line.get(3..).unwrap_or("").to_string())
-                    }
-                  } else {
-                    Node::Whitespace
-                  }
-                },
-                Clone::clone,
-              );
-
-              let mut parts = line.split_whitespace();
-
-              if let (Some("=>"), Some(to)) = (parts.next(), parts.next()) {
-                items.push((vec![ast_node], Some(to.to_string()), false));
-              } else {
-                items.push((vec![ast_node], None, pre));
-              }
-            }
-          } else if response.status() != &Status::Input
-            && response.status() != &Status::SensitiveInput
-          {
-            self.error = Some(response.meta().to_string());
-          }
+    match self.transports.fetch(&self.url, &self.config) {
+      Ok((url, response)) => {
+        if response.is_success() && !response.is_text() {
+          self.download(&url, &response.body);
 
-          if let Some(last_url) = self.capsule_history.last() {
-            if last_url.to_string() != self.url.to_string() {
-              self.capsule_history.push(self.url.clone());
-            }
+          return;
+        }
+
+        self.url = url;
+
+        if response.is_input() {
+          self.accept_response_input = true;
+          self.response_input_text = response.meta;
+        } else if response.is_success() {
+          if response.mime() == "text/gemini" {
+            self.load_gemtext(&response.text());
           } else {
-            self.capsule_history.push(self.url.clone());
+            self.load_text(&response.text());
           }
-        }
-        Err(error) => {
-          self.error = Some(error.to_string());
+        } else {
+          self.load_gemtext("");
 
-          return;
+          self.error = Some(response.meta);
         }
+
+        self.record_visit();
       }
+      Err(error) => {
+        self.error = Some(error);
+      }
+    }
+  }
 
-      items
-    });
+  /// Save a response which cannot be displayed, staying on the page it was
+  /// linked from
+  fn download(&mut self, url: &Url, body: &[u8]) {
+    match crate::download::save(url, body, &self.config) {
+      Ok(path) =>
+        self.message = Some(format!("Downloaded {}", path.display())),
+      Err(error) => self.error = Some(error),
+    }
+
+    if let Some(previous) = self.previous_capsule.clone() {
+      self.url = previous;
+    }
+  }
+
+  /// Request the current page again, keeping the scroll position and
+  /// marking the lines which changed
+  pub fn reload(&mut self) {
+    let source = self.source.clone();
+    let state = self.items.state.clone();
+
+    self.make_request();
+    self.items.restore(&state);
+
+    self.changed = crate::watch::changed_lines(&source, &self.source);
+  }
+
+  /// Replace the current page with the given Gemtext document
+  fn load_gemtext(&mut self, content: &str) {
+    self.changed.clear();
+    self.source = content.to_string();
+    self.items = StatefulList::with_items(crate::document::parse(content));
   }
 
+  /// Replace the current page with the given plain text document
+  fn load_text(&mut self, content: &str) {
+    self.changed.clear();
+    self.source = content.to_string();
+    self.items = StatefulList::with_items(crate::document::plain(content));
+  }
+
+  fn record_visit(&mut self) {
+    if let Some(last_url) = self.capsule_history.last() {
+      if last_url.to_string() != self.url.to_string() {
+        self.capsule_history.push(self.url.clone());
+      }
+    } else {
+      self.capsule_history.push(self.url.clone());
+    }
+  }
+
+  /// Draw `app` on `terminal` and handle terminal events until it quits,
+  /// ticking every `tick_rate`
+  ///
+  /// # Errors
+  ///
+  /// If the terminal could not be drawn to or read from.
   pub fn run<B: ratatui::backend::Backend>(
     terminal: &mut ratatui::Terminal<B>,
-    mut app: Self,
+    app: &mut Self,
     tick_rate: Duration,
   ) -> std::io::Result<()> {
     let mut last_tick = Instant::now();
     loop {
-      terminal.draw(|f| crate::ui::ui(f, &mut app))?;
+      if let Some(notification) = app
+        .update_check
+        .as_ref()
+        .and_then(|update_check| update_check.try_recv().ok())
+      {
+        app.notification = Some(notification);
+      }
+
+      if std::mem::take(&mut app.clear) {
+        terminal.clear()?;
+      }
+
+      terminal.draw(|f| crate::ui::ui(f, app))?;
 
       let timeout = tick_rate
         .checked_sub(last_tick.elapsed())
         .unwrap_or_else(|| Duration::from_secs(0));
 
       if event::poll(timeout)? {
-        if let event::Event::Key(key) = event::read()? {
-          if crate::input::handle_key_strokes(&mut app, key) {
-            return Ok(());
-          }
+        match event::read()? {
+          event::Event::Key(key)
+            if crate::input::handle_key_strokes(app, key) =>
+            return Ok(()),
+          event::Event::Mouse(mouse) =>
+            crate::input::handle_mouse(app, mouse),
+          _ => {}
+        }
+      }
+
+      if let Some(watch) = &mut app.watch {
+        if watch.url != app.url {
+          app.watch = None;
+        } else if watch.due() {
+          app.reload();
         }
       }
 
@@ -209,6 +405,267 @@ impl App {
     }
   }
 
+  /// Remember the open tabs for the next start if the configuration asks
+  /// for the last session to be restored
+  ///
+  /// # Errors
+  ///
+  /// If the session file could not be written.
+  pub fn save_session(&self) -> Result<(), String> {
+    if self.config.startup != Startup::Session {
+      return Ok(());
+    }
+
+    crate::session::save(self.current_tab, &self.tab_urls())
+  }
+
+  /// Select the next heading after the selected item
+  pub fn next_heading(&mut self) {
+    let headings = crate::toc::headings(&self.items.items);
+
+    if let Some(index) =
+      crate::toc::next(&headings, self.items.state.selected())
+    {
+      self.items.select(index);
+    }
+  }
+
+  /// Select the heading before the selected item
+  pub fn previous_heading(&mut self) {
+    let headings = crate::toc::headings(&self.items.items);
+
+    if let Some(index) =
+      crate::toc::previous(&headings, self.items.state.selected())
+    {
+      self.items.select(index);
+    }
+  }
+
+  /// Resolve `link` against the current page and open it
+  pub fn follow_link(&mut self, link: &str) {
+    match crate::url::resolve(self.base(), link) {
+      Ok(Some(url)) if url.scheme() == "misfin" => self.compose(&url),
+      Ok(Some(url)) => {
+        self.set_url(url);
+        self.make_request();
+      }
+      Ok(None) => {}
+      Err(error) => self.error = Some(error),
+    }
+  }
+
+  /// Write a message to the Misfin address `url` in an external editor and
+  /// send it
+  pub fn compose(&mut self, url: &Url) {
+    let mailbox = crate::misfin::mailbox(url);
+
+    self.clear = true;
+
+    let message = match crate::editor::edit("", "gmi", self.config.mouse) {
+      Ok(message) if message.trim().is_empty() => {
+        self.message = Some("Not sending an empty message".to_string());
+
+        return;
+      }
+      Ok(message) => message,
+      Err(error) => {
+        self.error = Some(error);
+
+        return;
+      }
+    };
+
+    match crate::misfin::send(url, &message, &self.config) {
+      Ok(response) if response.is_success() => {
+        self.message = Some(format!(
+          "Sent to {mailbox} ({} {})",
+          response.status, response.meta
+        ));
+
+        if let Err(error) = crate::misfin::log(url, &message) {
+          self.error = Some(format!("Could not record the message: {error}"));
+        }
+      }
+      Ok(response) =>
+        self.error = Some(format!(
+          "{mailbox} did not accept the message ({} {})",
+          response.status, response.meta
+        )),
+      Err(error) => self.error = Some(error),
+    }
+  }
+
+  /// Follow the link of the item at `index`, asking for the input to upload
+  /// first if it is a Spartan prompt line, or do nothing if there is no such
+  /// item
+  pub fn follow_item(&mut self, index: usize) {
+    let Some(Item {
+      nodes,
+      link,
+      prompt,
+      ..
+    }) = self.items.items.get(index)
+    else {
+      return;
+    };
+    let Some(link) = link.clone() else {
+      return;
+    };
+
+    if !prompt {
+      self.follow_link(&link);
+
+      return;
+    }
+
+    match crate::url::resolve(self.base(), &link) {
+      Ok(Some(url)) => {
+        self.response_input_text = match nodes.first() {
+          Some(germ::ast::Node::Link {
+            text: Some(text), ..
+          }) => text.clone(),
+          _ => "Input".to_string(),
+        };
+        self.upload = Some(url);
+        self.accept_response_input = true;
+      }
+      Ok(None) => {}
+      Err(error) => self.error = Some(error),
+    }
+  }
+
+  /// Copy `text` to the clipboard, reporting the outcome in the status bar
+  pub fn yank(&mut self, text: &str) {
+    match crate::clipboard::copy(text, self.config.clipboard_command.as_deref())
+    {
+      Ok(()) =>
+        self.message = Some(format!(
+          "Yanked {}",
+          if text.lines().count() > 1 {
+            format!("{} lines", text.lines().count())
+          } else {
+            format!("\"{text}\"")
+          }
+        )),
+      Err(error) => self.error = Some(error),
+    }
+  }
+
+  /// The inclusive range of items covered by the visual line selection
+  #[must_use]
+  pub fn visual_range(&self) -> (usize, usize) {
+    let selected = self.items.state.selected().unwrap_or(self.visual_anchor);
+
+    (
+      self.visual_anchor.min(selected),
+      self.visual_anchor.max(selected),
+    )
+  }
+
+  /// The index of the item rendered at the given terminal cell
+  #[must_use]
+  pub fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+    let area = self.list_area;
+
+    if column < area.x
+      || column >= area.x + area.width
+      || row < area.y
+      || row >= area.y + area.height
+    {
+      return None;
+    }
+
+    let mut row = usize::from(row - area.y);
+
+    for (index, height) in self
+      .item_heights
+      .iter()
+      .enumerate()
+      .skip(self.items.state.offset())
+    {
+      if row < *height {
+        return Some(index);
+      }
+
+      row -= height;
+    }
+
+    None
+  }
+
+  /// Open `url` in a new tab after the current one, without switching to it
+  pub fn open_tab(&mut self, url: Url) {
+    self.tabs.insert(self.current_tab + 1, Tab::new(url));
+  }
+
+  /// Display the tab at `index`, requesting its page if it was never loaded
+  pub fn switch_tab(&mut self, index: usize) {
+    if index == self.current_tab || index >= self.tabs.len() {
+      return;
+    }
+
+    self.swap_tab(self.current_tab);
+    self.tabs[self.current_tab].loaded = true;
+    self.swap_tab(index);
+
+    self.current_tab = index;
+
+    if !self.tabs[index].loaded {
+      self.tabs[index].loaded = true;
+
+      self.make_request();
+    }
+  }
+
+  /// Display the tab after the current one, wrapping around
+  pub fn next_tab(&mut self) {
+    self.switch_tab((self.current_tab + 1) % self.tabs.len());
+  }
+
+  /// Display the tab before the current one, wrapping around
+  pub fn previous_tab(&mut self) {
+    self.switch_tab(
+      self.current_tab.checked_sub(1).unwrap_or(self.tabs.len() - 1),
+    );
+  }
+
+  /// Close the current tab and display its neighbour
+  pub fn close_tab(&mut self) {
+    if self.tabs.len() == 1 {
+      self.error = Some("Cannot close the last tab".to_string());
+
+      return;
+    }
+
+    let closing = self.current_tab;
+
+    self.switch_tab(if closing + 1 < self.tabs.len() {
+      closing + 1
+    } else {
+      closing - 1
+    });
+    self.tabs.remove(closing);
+
+    if self.current_tab > closing {
+      self.current_tab -= 1;
+    }
+  }
+
+  /// Exchange the page state of the displayed tab with the tab stored at
+  /// `index`
+  fn swap_tab(&mut self, index: usize) {
+    let tab = &mut self.tabs[index];
+
+    std::mem::swap(&mut self.url, &mut tab.url);
+    std::mem::swap(&mut self.items, &mut tab.items);
+    std::mem::swap(&mut self.capsule_history, &mut tab.capsule_history);
+    std::mem::swap(&mut self.previous_capsule, &mut tab.previous_capsule);
+    std::mem::swap(&mut self.source, &mut tab.source);
+
+    self.changed.clear();
+  }
+
+  /// Return to the previous page of the current tab's history
   pub fn go_back(&mut self) {
     if let Some(url) = self.capsule_history.pop() {
       if url == self.url {
@@ -0,0 +1,66 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Bookmarks are stored one URL per line in the `bookmarks` file of Sydney's
+//! data directory
+
+use std::{io::Write, path::PathBuf};
+
+fn path() -> Option<PathBuf> {
+  crate::config::data_directory().map(|directory| directory.join("bookmarks"))
+}
+
+/// Every bookmarked URL, in the order they were added
+#[must_use]
+pub fn load() -> Vec<String> {
+  path()
+    .and_then(|path| std::fs::read_to_string(path).ok())
+    .map(|content| {
+      content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToString::to_string)
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Append `url` to the bookmarks unless it is already bookmarked
+///
+/// # Errors
+///
+/// If the bookmarks file could not be written.
+pub fn add(url: &str) -> Result<(), String> {
+  if load().iter().any(|bookmark| bookmark == url) {
+    return Err(format!("{url} is already bookmarked"));
+  }
+
+  let path = path()
+    .ok_or_else(|| "Could not locate the data directory".to_string())?;
+
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+  }
+
+  std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+    .and_then(|mut file| writeln!(file, "{url}"))
+    .map_err(|error| error.to_string())
+}
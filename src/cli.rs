@@ -0,0 +1,452 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sydney's command-line arguments
+
+use std::{io::IsTerminal, path::PathBuf};
+
+use url::Url;
+
+use sydney::{config::Config, dump, export::Format, theme::Theme};
+
+pub const HELP: &str = r"usage: sydney [options] [url...]
+       sydney [options] [--base <url>] -
+       sydney [options] fetch [fetch options] <url>
+       sydney [options] bookmarks list
+       sydney [options] bookmarks add <url>
+
+Commands:
+    <url...>             open each URL, or path to a local file, in a tab
+    -                    read a Gemtext document from standard input, which
+                         is also done when standard input is not a terminal
+    fetch <url>          print a page to standard output and exit
+    bookmarks list       print every bookmark
+    bookmarks add <url>  bookmark a URL
+
+Options:
+    --config <path>      read the configuration from a file
+    --no-update-check    do not check for newer versions of Sydney
+    --identity <name>    present the client certificate of an identity to
+                         the hosts of the given URLs
+    --theme <theme>      use the dark, light or terminal theme
+    --wrap <width>       wrap text at a width instead of the terminal width
+    --base <url>         resolve links of a document read from standard
+                         input against a URL
+    --version, -v        show version text
+    --help, -h           show help text
+
+Fetch options:
+    --raw                print the body exactly as it was received
+    --format <format>    convert the page to html, markdown or text
+    --header             print the response header before the body
+    --color              style the page with ANSI escape sequences, the
+                         default when printing to a terminal
+    --no-color           print the page as plain text
+
+Sample invocations:
+    sydney gemini://fuwn.me/
+    sydney fuwn.me geminiprotocol.net
+    sydney --theme light --wrap 72 fuwn.me
+    sydney fetch --no-color fuwn.me | less
+    make-gemlog | sydney --base gemini://fuwn.me/ -
+    sydney --help
+
+Report bugs to https://github.com/gemrest/sydney/issues";
+
+pub enum Command {
+  /// Open the interface with a tab for each of the given locations
+  Browse(Vec<String>),
+  Fetch(dump::Options),
+  BookmarksList,
+  BookmarksAdd(String),
+  Help,
+  Version,
+}
+
+pub struct Cli {
+  pub config:          Option<PathBuf>,
+  pub no_update_check: bool,
+  pub identity:        Option<String>,
+  pub theme:           Option<Theme>,
+  pub wrap:            Option<u16>,
+  /// The URL links of a document read from standard input are relative to
+  pub base:            Option<Url>,
+  pub command:         Command,
+}
+impl Cli {
+  /// Override the configuration with the options given on the command line
+  pub fn configure(&self, config: &mut Config) {
+    if self.no_update_check {
+      config.update_check = false;
+    }
+
+    if let Some(identity) = &self.identity {
+      let locations = match &self.command {
+        Command::Browse(locations) => locations.as_slice(),
+        Command::Fetch(options) => std::slice::from_ref(&options.location),
+        _ => &[],
+      };
+
+      for location in locations.iter().filter(|location| *location != "-") {
+        if let Some(host) = sydney::url::parse_location(location)
+          .ok()
+          .as_ref()
+          .and_then(Url::host_str)
+        {
+          config
+            .identities
+            .insert(host.to_lowercase(), identity.clone());
+        }
+      }
+    }
+
+    if let Some(theme) = self.theme {
+      config.theme = theme;
+    }
+  }
+}
+
+/// Parse the command-line arguments, excluding the program name
+#[allow(clippy::too_many_lines)]
+pub fn parse(
+  arguments: impl IntoIterator<Item = String>,
+) -> Result<Cli, String> {
+  let mut arguments = arguments.into_iter();
+  let mut cli = Cli {
+    config:          None,
+    no_update_check: false,
+    identity:        None,
+    theme:           None,
+    wrap:            None,
+    base:            None,
+    command:         Command::Help,
+  };
+  let mut fetch = dump::Options {
+    location: String::new(),
+    raw:      false,
+    header:   false,
+    color:    std::io::stdout().is_terminal(),
+    format:   None,
+    wrap_at:  None,
+    base:     None,
+  };
+  // The first option given which only applies to `fetch`
+  let mut fetch_option = None;
+  let mut positionals = vec![];
+  let (mut help, mut version, mut dump) = (false, false, false);
+
+  while let Some(argument) = arguments.next() {
+    if argument == "--" {
+      positionals.extend(arguments.by_ref());
+
+      break;
+    }
+
+    let (option, mut inline) = match argument.split_once('=') {
+      Some((option, value)) if argument.starts_with("--") =>
+        (option.to_string(), Some(value.to_string())),
+      _ => (argument.clone(), None),
+    };
+
+    match option.as_str() {
+      "--help" | "-h" => help = true,
+      "--version" | "-v" => version = true,
+      "--config" =>
+        cli.config = Some(value(&option, &mut inline, &mut arguments)?.into()),
+      "--no-update-check" => cli.no_update_check = true,
+      "--identity" =>
+        cli.identity = Some(value(&option, &mut inline, &mut arguments)?),
+      "--theme" =>
+        cli.theme =
+          Some(Theme::parse(&value(&option, &mut inline, &mut arguments)?)?),
+      "--wrap" => {
+        let width = value(&option, &mut inline, &mut arguments)?;
+
+        cli.wrap = match width.parse::<u16>() {
+          Ok(0) => None,
+          Ok(width) => Some(width),
+          Err(_) => return Err(format!("\"{width}\" is not a valid width")),
+        };
+      }
+      "--base" =>
+        cli.base = Some(sydney::url::parse_location(&value(
+          &option,
+          &mut inline,
+          &mut arguments,
+        )?)?),
+      "--dump" => dump = true,
+      "--raw" | "--header" | "--color" | "--no-color" | "--format" => {
+        match option.as_str() {
+          "--raw" => fetch.raw = true,
+          "--header" => fetch.header = true,
+          "--color" => fetch.color = true,
+          "--no-color" => fetch.color = false,
+          _ =>
+            fetch.format = Some(Format::parse(&value(
+              &option,
+              &mut inline,
+              &mut arguments,
+            )?)?),
+        }
+
+        fetch_option.get_or_insert_with(|| option.clone());
+      }
+      _ if option.starts_with('-') && option.len() > 1 =>
+        return Err(format!("Unknown option \"{option}\"")),
+      _ => positionals.push(argument),
+    }
+
+    if inline.is_some() {
+      return Err(format!("{option} does not take a value"));
+    }
+  }
+
+  cli.command = if help {
+    Command::Help
+  } else if version {
+    Command::Version
+  } else if dump {
+    fetch_command(fetch, positionals)?
+  } else {
+    match positionals.first().map(String::as_str) {
+      Some("fetch") => fetch_command(fetch, positionals.split_off(1))?,
+      Some("bookmarks") =>
+        match positionals.get(1..).unwrap_or_default() {
+          [list] if list == "list" => Command::BookmarksList,
+          [add, url] if add == "add" => Command::BookmarksAdd(url.clone()),
+          _ =>
+            return Err(
+              "usage: sydney bookmarks list | sydney bookmarks add <url>"
+                .to_string(),
+            ),
+        },
+      _ => Command::Browse(positionals),
+    }
+  };
+
+  if let (Some(option), false) =
+    (fetch_option, matches!(cli.command, Command::Fetch(_)))
+  {
+    return Err(format!("{option} can only be used with fetch"));
+  }
+
+  if let Command::Fetch(options) = &mut cli.command {
+    if cli.base.is_some() && options.location != "-" {
+      return Err(
+        "--base can only be used when reading from standard input"
+          .to_string(),
+      );
+    }
+
+    options.wrap_at = cli.wrap;
+    options.base.clone_from(&cli.base);
+  }
+
+  Ok(cli)
+}
+
+/// The value of `option`, given either as `--option=value` or as the next
+/// argument
+fn value(
+  option: &str,
+  inline: &mut Option<String>,
+  arguments: &mut impl Iterator<Item = String>,
+) -> Result<String, String> {
+  inline
+    .take()
+    .or_else(|| arguments.next())
+    .ok_or_else(|| format!("{option} requires a value"))
+}
+
+fn fetch_command(
+  mut options: dump::Options,
+  locations: Vec<String>,
+) -> Result<Command, String> {
+  let [location] = <[String; 1]>::try_from(locations)
+    .map_err(|_| "fetch takes exactly one URL".to_string())?;
+
+  if options.raw && options.format.is_some() {
+    return Err("--raw and --format cannot be combined".to_string());
+  }
+
+  options.location = location;
+
+  Ok(Command::Fetch(options))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fmt::Write;
+
+  use super::*;
+
+  fn parse_line(line: &str) -> Result<Cli, String> {
+    parse(line.split_whitespace().map(String::from))
+  }
+
+  /// The command `cli` runs, in a form which is easy to compare
+  fn describe(cli: &Cli) -> String {
+    match &cli.command {
+      Command::Browse(locations) => format!("browse {}", locations.join(" ")),
+      Command::Fetch(options) => {
+        let mut description = format!("fetch {}", options.location);
+
+        if options.raw {
+          description.push_str(" raw");
+        }
+
+        if options.header {
+          description.push_str(" header");
+        }
+
+        if let Some(format) = options.format {
+          description.push_str(match format {
+            Format::Markdown => " markdown",
+            Format::Html => " html",
+            Format::Text => " text",
+          });
+        }
+
+        if let Some(wrap_at) = options.wrap_at {
+          let _ = write!(description, " wrap={wrap_at}");
+        }
+
+        if let Some(base) = &options.base {
+          let _ = write!(description, " base={base}");
+        }
+
+        description
+      }
+      Command::BookmarksList => "bookmarks list".to_string(),
+      Command::BookmarksAdd(url) => format!("bookmarks add {url}"),
+      Command::Help => "help".to_string(),
+      Command::Version => "version".to_string(),
+    }
+    .trim_end()
+    .to_string()
+  }
+
+  #[test]
+  fn parses() {
+    for (line, expected) in [
+      ("", "browse"),
+      (
+        "fuwn.me gemini://example.com/",
+        "browse fuwn.me gemini://example.com/",
+      ),
+      ("-", "browse -"),
+      ("--wrap 72 -", "browse -"),
+      ("-- --help -v", "browse --help -v"),
+      ("--help fuwn.me", "help"),
+      ("-h", "help"),
+      ("--version", "version"),
+      ("-v --help", "help"),
+      ("fetch fuwn.me", "fetch fuwn.me"),
+      ("fetch --raw --header fuwn.me", "fetch fuwn.me raw header"),
+      ("--format=html fetch fuwn.me", "fetch fuwn.me html"),
+      ("fetch fuwn.me --format markdown", "fetch fuwn.me markdown"),
+      ("--wrap=72 fetch fuwn.me", "fetch fuwn.me wrap=72"),
+      ("--wrap 0 fetch fuwn.me", "fetch fuwn.me"),
+      (
+        "fetch --base gemini://fuwn.me/ -",
+        "fetch - base=gemini://fuwn.me/",
+      ),
+      ("--dump fuwn.me", "fetch fuwn.me"),
+      ("fetch -- --raw", "fetch --raw"),
+      ("bookmarks list", "bookmarks list"),
+      ("bookmarks add fuwn.me", "bookmarks add fuwn.me"),
+    ] {
+      assert_eq!(
+        parse_line(line).as_ref().map(describe),
+        Ok(expected.to_string()),
+        "{line:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn parses_options() {
+    let cli = parse_line(
+      "--config=/tmp/config --no-update-check --identity work --theme light \
+       --base gemini://fuwn.me/ -",
+    )
+    .unwrap();
+
+    assert_eq!(cli.config, Some(PathBuf::from("/tmp/config")));
+    assert!(cli.no_update_check);
+    assert_eq!(cli.identity.as_deref(), Some("work"));
+    assert!(cli.theme == Some(Theme::Light));
+    assert_eq!(
+      cli.base.map(String::from).as_deref(),
+      Some("gemini://fuwn.me/")
+    );
+
+    for (line, color) in [
+      ("fetch --color fuwn.me", true),
+      ("fetch --no-color fuwn.me", false),
+    ] {
+      match parse_line(line).unwrap().command {
+        Command::Fetch(options) => assert_eq!(options.color, color, "{line:?}"),
+        _ => panic!("{line:?} is not a fetch"),
+      }
+    }
+  }
+
+  #[test]
+  fn rejects_invalid_arguments() {
+    for (line, expected) in [
+      ("--bogus", "Unknown option \"--bogus\""),
+      ("-x fuwn.me", "Unknown option \"-x\""),
+      ("--help=yes", "--help does not take a value"),
+      ("--wrap", "--wrap requires a value"),
+      ("--wrap wide", "\"wide\" is not a valid width"),
+      ("--raw fuwn.me", "--raw can only be used with fetch"),
+      (
+        "--no-color --header fuwn.me",
+        "--no-color can only be used with fetch",
+      ),
+      (
+        "fetch --raw --format text fuwn.me",
+        "--raw and --format cannot be combined",
+      ),
+      ("fetch", "fetch takes exactly one URL"),
+      ("fetch fuwn.me example.com", "fetch takes exactly one URL"),
+      (
+        "fetch --base gemini://fuwn.me/ fuwn.me",
+        "--base can only be used when reading from standard input",
+      ),
+      (
+        "bookmarks",
+        "usage: sydney bookmarks list | sydney bookmarks add <url>",
+      ),
+      (
+        "bookmarks remove fuwn.me",
+        "usage: sydney bookmarks list | sydney bookmarks add <url>",
+      ),
+    ] {
+      assert_eq!(
+        parse_line(line).err().as_deref(),
+        Some(expected),
+        "{line:?}"
+      );
+    }
+
+    for line in ["--theme neon", "fetch --format pdf fuwn.me"] {
+      assert!(parse_line(line).is_err(), "{line:?}");
+    }
+  }
+}
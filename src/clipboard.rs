@@ -0,0 +1,83 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::io::Write;
+
+const BASE64: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copy `text` to the system clipboard
+///
+/// If `command` is set, `text` is piped to it. Otherwise, the terminal is
+/// asked to set the clipboard using an OSC 52 escape sequence, which also
+/// works over SSH.
+pub fn copy(text: &str, command: Option<&str>) -> Result<(), String> {
+  if let Some(command) = command {
+    let mut arguments = command.split_whitespace();
+    let program = arguments
+      .next()
+      .ok_or_else(|| "The clipboard command is empty".to_string())?;
+    let mut child = std::process::Command::new(program)
+      .args(arguments)
+      .stdin(std::process::Stdio::piped())
+      .stdout(std::process::Stdio::null())
+      .stderr(std::process::Stdio::null())
+      .spawn()
+      .map_err(|error| format!("Could not run \"{program}\": {error}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+      stdin
+        .write_all(text.as_bytes())
+        .map_err(|error| error.to_string())?;
+    }
+
+    let status = child.wait().map_err(|error| error.to_string())?;
+
+    if status.success() {
+      Ok(())
+    } else {
+      Err(format!("\"{program}\" exited with {status}"))
+    }
+  } else {
+    let mut stdout = std::io::stdout();
+
+    write!(stdout, "\x1b]52;c;{}\x07", encode(text.as_bytes()))
+      .and_then(|()| stdout.flush())
+      .map_err(|error| error.to_string())
+  }
+}
+
+fn encode(bytes: &[u8]) -> String {
+  let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+  for chunk in bytes.chunks(3) {
+    let group = chunk
+      .iter()
+      .enumerate()
+      .fold(0u32, |group, (i, byte)| group | u32::from(*byte) << (16 - i * 8));
+
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(BASE64[(group >> (18 - i * 6)) as usize & 0x3f] as char);
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+
+  encoded
+}
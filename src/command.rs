@@ -15,45 +15,734 @@
 // Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
-pub enum Command {
-  Quit,
-  Open(Option<String>),
-  Unknown,
-  Wrap(u16, Option<String>),
-  Help,
-}
-impl From<String> for Command {
-  fn from(s: String) -> Self {
-    let mut tokens = s.split(' ');
-
-    match tokens.next() {
-      Some("open" | "o") => Self::Open(tokens.next().map(ToString::to_string)),
-      Some("quit" | "q") => Self::Quit,
-      Some("wrap") =>
-        tokens.next().map_or_else(
-          || {
-            Self::Wrap(
-              80,
-              Some("Missing width argument to wrap command".to_string()),
-            )
-          },
-          |at| {
-            match at.parse() {
-              Ok(at_parsed) =>
-                Self::Wrap(
-                  if at_parsed == 0 {
-                    crossterm::terminal::size().unwrap_or((80, 24)).0
-                  } else {
-                    at_parsed
-                  },
-                  None,
-                ),
-              Err(error) => Self::Wrap(80, Some(error.to_string())),
-            }
+//! The `:` command language
+//!
+//! A command line is split into words by whitespace. Words may be quoted with
+//! `"` (where `\` escapes the next character) or `'` (taken literally), and
+//! outside of quotes `\` escapes the next character, so
+//! `:open "gemini://example.com/a b"` and `:open gemini://example.com/a\ b`
+//! are equivalent. The first word names a command from [`COMMANDS`], and the
+//! remaining words are checked against that command's arguments before its
+//! handler runs.
+
+use std::fmt;
+
+use url::Url;
+
+pub struct Definition {
+  pub name:      &'static str,
+  pub aliases:   &'static [&'static str],
+  pub arguments: &'static [Argument],
+  pub help:      &'static str,
+  /// Runs the command, returning whether Sydney should quit
+  pub handler:   fn(&mut crate::App, &Arguments) -> bool,
+}
+impl Definition {
+  /// The usage line of the command, e.g., `open <url>`
+  pub fn usage(&self) -> String {
+    self.arguments.iter().fold(self.name.to_string(), |usage, argument| {
+      format!("{usage} {argument}")
+    })
+  }
+}
+
+pub struct Argument {
+  pub name:  &'static str,
+  pub kind:  Kind,
+  pub arity: Arity,
+}
+impl fmt::Display for Argument {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.arity {
+      Arity::Required => write!(f, "<{}>", self.name),
+      Arity::Optional => write!(f, "[{}]", self.name),
+      Arity::Rest => write!(f, "[{}...]", self.name),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+  Text,
+  Integer,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+  Required,
+  Optional,
+  /// Consumes every remaining word, joined by single spaces
+  Rest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+  Text(String),
+  Integer(u64),
+}
+
+/// The values of a command's arguments, in the order they are defined
+pub struct Arguments(Vec<Option<Value>>);
+impl Arguments {
+  pub fn text(&self, index: usize) -> Option<&str> {
+    match self.0.get(index)? {
+      Some(Value::Text(text)) => Some(text),
+      _ => None,
+    }
+  }
+
+  pub fn integer(&self, index: usize) -> Option<u64> {
+    match self.0.get(index)? {
+      Some(Value::Integer(integer)) => Some(*integer),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Token {
+  pub text:     String,
+  /// The character column the token starts at
+  pub position: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+  UnterminatedQuote,
+  TrailingEscape,
+  UnknownCommand(String),
+  MissingArgument(&'static str),
+  UnexpectedArgument(String),
+  InvalidInteger(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+  pub kind:     ErrorKind,
+  /// The character column the error was found at
+  pub position: usize,
+}
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.kind {
+      ErrorKind::UnterminatedQuote => write!(f, "Unterminated quote"),
+      ErrorKind::TrailingEscape => write!(f, "Nothing to escape"),
+      ErrorKind::UnknownCommand(name) =>
+        write!(f, "\"{name}\" is not a valid command"),
+      ErrorKind::MissingArgument(name) =>
+        write!(f, "Missing argument <{name}>"),
+      ErrorKind::UnexpectedArgument(argument) =>
+        write!(f, "Unexpected argument \"{argument}\""),
+      ErrorKind::InvalidInteger(integer) =>
+        write!(f, "\"{integer}\" is not a valid number"),
+    }?;
+
+    write!(f, " at column {}", self.position + 1)
+  }
+}
+
+const fn argument(name: &'static str, kind: Kind, arity: Arity) -> Argument {
+  Argument {
+    name,
+    kind,
+    arity,
+  }
+}
+
+pub static COMMANDS: &[Definition] = &[
+  Definition {
+    name:      "bookmark",
+    aliases:   &["b"],
+    arguments: &[],
+    help:      "Bookmark the current page",
+    handler:   bookmark,
+  },
+  Definition {
+    name:      "edit",
+    aliases:   &["e"],
+    arguments: &[argument("path", Kind::Text, Arity::Optional)],
+    help:      "Open a local file, or read the current page again",
+    handler:   edit,
+  },
+  Definition {
+    name:      "export",
+    aliases:   &[],
+    arguments: &[
+      argument("format", Kind::Text, Arity::Required),
+      argument("path", Kind::Text, Arity::Required),
+    ],
+    help:      "Save the current page as Markdown, HTML or plain text",
+    handler:   export,
+  },
+  Definition {
+    name:      "help",
+    aliases:   &["h"],
+    arguments: &[argument("command", Kind::Text, Arity::Optional)],
+    help:      "Show help, or the usage of a command",
+    handler:   help,
+  },
+  Definition {
+    name:      "misfin",
+    aliases:   &[],
+    arguments: &[argument("address", Kind::Text, Arity::Required)],
+    help:      "Write a message to a Misfin address in $EDITOR and send it",
+    handler:   misfin,
+  },
+  Definition {
+    name:      "open",
+    aliases:   &["o"],
+    arguments: &[argument("url", Kind::Text, Arity::Required)],
+    help:      "Open a URL",
+    handler:   open,
+  },
+  Definition {
+    name:      "quit",
+    aliases:   &["q"],
+    arguments: &[],
+    help:      "Quit Sydney",
+    handler:   |_, _| true,
+  },
+  Definition {
+    name:      "tabclose",
+    aliases:   &["tabc"],
+    arguments: &[],
+    help:      "Close the current tab",
+    handler:   |app, _| {
+      app.close_tab();
+
+      false
+    },
+  },
+  Definition {
+    name:      "tabnew",
+    aliases:   &[],
+    arguments: &[argument("url", Kind::Text, Arity::Optional)],
+    help:      "Open a URL, or the current page, in a new tab",
+    handler:   tab_new,
+  },
+  Definition {
+    name:      "titan",
+    aliases:   &[],
+    arguments: &[argument("url", Kind::Text, Arity::Optional)],
+    help:      "Edit the current page, or the page at a Gemini or Titan URL, \
+                in $EDITOR and upload it over Titan",
+    handler:   titan,
+  },
+  Definition {
+    name:      "toc",
+    aliases:   &[],
+    arguments: &[argument("heading", Kind::Text, Arity::Rest)],
+    help:      "Jump to the heading best matching a name, or toggle the table \
+                of contents",
+    handler:   toc,
+  },
+  Definition {
+    name:      "watch",
+    aliases:   &[],
+    arguments: &[argument("seconds", Kind::Integer, Arity::Optional)],
+    help:      "Reload the current page every few seconds, or a local file \
+                whenever it changes, or stop doing so",
+    handler:   watch,
+  },
+  Definition {
+    name:      "wrap",
+    aliases:   &[],
+    arguments: &[argument("width", Kind::Integer, Arity::Required)],
+    help:      "Wrap text at a width, or at the terminal width if zero",
+    handler:   wrap,
+  },
+];
+
+pub fn find(name: &str) -> Option<&'static Definition> {
+  COMMANDS
+    .iter()
+    .find(|command| command.name == name || command.aliases.contains(&name))
+}
+
+/// Parse and run a command line, returning whether Sydney should quit
+pub fn execute(app: &mut crate::App, line: &str) -> bool {
+  match parse(line) {
+    Ok(Some((command, arguments))) => (command.handler)(app, &arguments),
+    Ok(None) => false,
+    Err(error) => {
+      app.error = Some(error.to_string());
+
+      false
+    }
+  }
+}
+
+/// Parse a command line into the command it names and its arguments, or
+/// `None` if the line is blank
+pub fn parse(
+  line: &str,
+) -> Result<Option<(&'static Definition, Arguments)>, ParseError> {
+  let mut tokens = tokenize(line)?.into_iter();
+  let Some(name) = tokens.next() else {
+    return Ok(None);
+  };
+  let command = find(&name.text).ok_or(ParseError {
+    kind:     ErrorKind::UnknownCommand(name.text),
+    position: name.position,
+  })?;
+  let mut values = Vec::with_capacity(command.arguments.len());
+
+  for argument in command.arguments {
+    let token = if argument.arity == Arity::Rest {
+      let rest = tokens.by_ref().collect::<Vec<_>>();
+
+      rest.first().map(|first| {
+        Token {
+          position: first.position,
+          text:     rest
+            .iter()
+            .map(|token| token.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        }
+      })
+    } else {
+      tokens.next()
+    };
+    let Some(token) = token else {
+      if argument.arity == Arity::Required {
+        return Err(ParseError {
+          kind:     ErrorKind::MissingArgument(argument.name),
+          position: line.chars().count(),
+        });
+      }
+
+      values.push(None);
+
+      continue;
+    };
+
+    values.push(Some(match argument.kind {
+      Kind::Text => Value::Text(token.text),
+      Kind::Integer =>
+        Value::Integer(token.text.parse().map_err(|_| {
+          ParseError {
+            position: token.position,
+            kind:     ErrorKind::InvalidInteger(token.text),
+          }
+        })?),
+    }));
+  }
+
+  if let Some(token) = tokens.next() {
+    return Err(ParseError {
+      kind:     ErrorKind::UnexpectedArgument(token.text),
+      position: token.position,
+    });
+  }
+
+  Ok(Some((command, Arguments(values))))
+}
+
+/// Split a command line into words, honouring quotes and escapes
+pub fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+  let mut tokens = vec![];
+  let mut current: Option<Token> = None;
+  let mut characters = line.chars().enumerate();
+
+  while let Some((position, character)) = characters.next() {
+    if character.is_whitespace() {
+      tokens.extend(current.take());
+
+      continue;
+    }
+
+    let token = current.get_or_insert_with(|| {
+      Token {
+        text: String::new(),
+        position,
+      }
+    });
+
+    match character {
+      '\\' =>
+        token.text.push(characters.next().map(|(_, c)| c).ok_or(
+          ParseError {
+            kind: ErrorKind::TrailingEscape,
+            position,
           },
-        ),
-      Some("help" | "h") => Self::Help,
-      _ => Self::Unknown,
+        )?),
+      '"' | '\'' => {
+        let quote = character;
+
+        loop {
+          match characters.next() {
+            Some((_, c)) if c == quote => break,
+            Some((escape, '\\')) if quote == '"' =>
+              token.text.push(characters.next().map(|(_, c)| c).ok_or(
+                ParseError {
+                  kind:     ErrorKind::TrailingEscape,
+                  position: escape,
+                },
+              )?),
+            Some((_, c)) => token.text.push(c),
+            None =>
+              return Err(ParseError {
+                kind: ErrorKind::UnterminatedQuote,
+                position,
+              }),
+          }
+        }
+      }
+      _ => token.text.push(character),
+    }
+  }
+
+  tokens.extend(current);
+
+  Ok(tokens)
+}
+
+fn bookmark(app: &mut crate::App, _: &Arguments) -> bool {
+  let url = app.url.to_string();
+
+  match crate::bookmarks::add(&url) {
+    Ok(()) => app.message = Some(format!("Bookmarked {url}")),
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn edit(app: &mut crate::App, arguments: &Arguments) -> bool {
+  if let Some(path) = arguments.text(0) {
+    match crate::url::parse_location(path) {
+      Ok(url) if url.scheme() == "file" => app.set_url(url),
+      Ok(_) => {
+        app.error = Some(format!("{path} is not a local file"));
+
+        return false;
+      }
+      Err(error) => {
+        app.error = Some(error);
+
+        return false;
+      }
+    }
+  }
+
+  app.error = None;
+
+  app.make_request();
+
+  false
+}
+
+fn export(app: &mut crate::App, arguments: &Arguments) -> bool {
+  let format = arguments.text(0).unwrap_or_default();
+  let path = arguments.text(1).unwrap_or_default();
+  let result = crate::export::Format::parse(format).and_then(|format| {
+    let path = crate::url::expand_path(path)?;
+
+    std::fs::write(
+      &path,
+      crate::export::export(&app.source, app.base(), format),
+    )
+    .map(|()| path.display().to_string())
+    .map_err(|error| format!("Could not write {}: {error}", path.display()))
+  });
+
+  match result {
+    Ok(path) => app.message = Some(format!("Exported to {path}")),
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn help(app: &mut crate::App, arguments: &Arguments) -> bool {
+  if let Some(name) = arguments.text(0) {
+    match find(name) {
+      Some(command) =>
+        app.message = Some(format!(":{} - {}", command.usage(), command.help)),
+      None => app.error = Some(format!("\"{name}\" is not a valid command")),
+    }
+
+    return false;
+  }
+
+  app.set_url(Url::parse("about:help").unwrap());
+  app.make_request();
+
+  false
+}
+
+fn misfin(app: &mut crate::App, arguments: &Arguments) -> bool {
+  match crate::misfin::address(arguments.text(0).unwrap_or_default()) {
+    Ok(url) => app.compose(&url),
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn open(app: &mut crate::App, arguments: &Arguments) -> bool {
+  match crate::url::parse_location(arguments.text(0).unwrap_or_default()) {
+    Ok(url) => {
+      app.set_url(url);
+      app.make_request();
+    }
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn tab_new(app: &mut crate::App, arguments: &Arguments) -> bool {
+  let url = arguments
+    .text(0)
+    .map_or_else(|| Ok(app.url.clone()), crate::url::parse_location);
+
+  match url {
+    Ok(url) => {
+      app.open_tab(url);
+      app.next_tab();
+    }
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn titan(app: &mut crate::App, arguments: &Arguments) -> bool {
+  let url = arguments
+    .text(0)
+    .map_or_else(|| Ok(app.url.clone()), crate::url::parse_location)
+    .and_then(|url| {
+      if matches!(url.scheme(), "gemini" | "titan") {
+        Ok(url)
+      } else {
+        Err(format!("{url} cannot be edited over Titan"))
+      }
+    });
+  let result = url.and_then(|url| {
+    let page = crate::titan::page(&url)?;
+    let current = crate::titan::page(&app.url).is_ok_and(|url| url == page);
+    let source = if current { app.source.clone() } else { String::new() };
+
+    app.clear = true;
+
+    let edited = crate::editor::edit(&source, "gmi", app.config.mouse)?;
+
+    if edited == source {
+      return Ok(None);
+    }
+
+    let response = crate::titan::upload(&url, edited.as_bytes(), &app.config)?;
+
+    if response.is_redirect() {
+      let target = url.join(&response.meta).map_err(|error| {
+        format!("Could not follow the redirect to {}: {error}", response.meta)
+      })?;
+
+      crate::titan::page(&target).map(Some)
+    } else if response.is_success() {
+      Ok(Some(page))
+    } else {
+      Err(format!("Could not upload {page}: {}", response.meta))
+    }
+  });
+
+  match result {
+    Ok(Some(page)) => {
+      app.message = Some(format!("Uploaded {page}"));
+
+      app.set_url(page);
+      app.make_request();
+    }
+    Ok(None) => app.message = Some("Nothing changed".to_string()),
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn toc(app: &mut crate::App, arguments: &Arguments) -> bool {
+  if let Some(query) = arguments.text(0) {
+    let headings = crate::toc::headings(&app.items.items);
+
+    if let Some(index) = crate::toc::find(&headings, query) {
+      app.items.select(index);
+    } else {
+      app.error = Some(format!("No heading matches \"{query}\""));
+    }
+  } else {
+    app.show_toc = !app.show_toc;
+  }
+
+  false
+}
+
+fn watch(app: &mut crate::App, arguments: &Arguments) -> bool {
+  let seconds = arguments.integer(0);
+
+  if seconds.is_none() && app.watch.take().is_some() {
+    app.message = Some("Stopped watching".to_string());
+
+    return false;
+  }
+
+  let every = match seconds {
+    Some(0) => {
+      app.error = Some("The interval must be at least one second".to_string());
+
+      return false;
+    }
+    Some(seconds) => Some(std::time::Duration::from_secs(seconds)),
+    None => None,
+  };
+
+  match crate::watch::Watch::new(app.url.clone(), every) {
+    Ok(watch) => {
+      app.message = Some(watch.describe());
+      app.watch = Some(watch);
+    }
+    Err(error) => app.error = Some(error),
+  }
+
+  false
+}
+
+fn wrap(app: &mut crate::App, arguments: &Arguments) -> bool {
+  match u16::try_from(arguments.integer(0).unwrap_or_default()) {
+    Ok(0) => app.wrap_at = crossterm::terminal::size().unwrap_or((80, 24)).0,
+    Ok(at) => app.wrap_at = at,
+    Err(error) => {
+      app.error = Some(error.to_string());
+
+      return false;
+    }
+  }
+
+  app.error = None;
+
+  app.make_request();
+
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn token(text: &str, position: usize) -> Token {
+    Token {
+      text: text.to_string(),
+      position,
+    }
+  }
+
+  const fn error(kind: ErrorKind, position: usize) -> ParseError {
+    ParseError {
+      kind,
+      position,
+    }
+  }
+
+  #[test]
+  fn tokenizes() {
+    for (line, expected) in [
+      ("", vec![]),
+      ("   ", vec![]),
+      (
+        "open  fuwn.me ",
+        vec![token("open", 0), token("fuwn.me", 6)],
+      ),
+      (
+        "toc \"Read me\"",
+        vec![token("toc", 0), token("Read me", 4)],
+      ),
+      ("toc 'a \"b\"'", vec![token("toc", 0), token("a \"b\"", 4)]),
+      (
+        "toc \"a \\\"b\\\"\"",
+        vec![token("toc", 0), token("a \"b\"", 4)],
+      ),
+      ("toc 'a\\b'", vec![token("toc", 0), token("a\\b", 4)]),
+      (
+        "edit my\\ file",
+        vec![token("edit", 0), token("my file", 5)],
+      ),
+      (
+        "edit pre\"fix\"ed",
+        vec![token("edit", 0), token("prefixed", 5)],
+      ),
+      ("edit \"\"", vec![token("edit", 0), token("", 5)]),
+      (
+        "open 日本 語",
+        vec![token("open", 0), token("日本", 5), token("語", 8)],
+      ),
+    ] {
+      assert_eq!(tokenize(line), Ok(expected), "{line:?}");
+    }
+  }
+
+  #[test]
+  fn rejects_malformed_quoting() {
+    for (line, expected) in [
+      ("toc \"open", error(ErrorKind::UnterminatedQuote, 4)),
+      ("toc 'open", error(ErrorKind::UnterminatedQuote, 4)),
+      ("toc open\\", error(ErrorKind::TrailingEscape, 8)),
+      ("toc \"open\\", error(ErrorKind::TrailingEscape, 9)),
+    ] {
+      assert_eq!(tokenize(line), Err(expected), "{line:?}");
+    }
+  }
+
+  #[test]
+  fn parses() {
+    for (line, name, values) in [
+      ("q", "quit", vec![]),
+      (
+        "o fuwn.me",
+        "open",
+        vec![Some(Value::Text("fuwn.me".to_string()))],
+      ),
+      ("tabnew", "tabnew", vec![None]),
+      ("wrap 72", "wrap", vec![Some(Value::Integer(72))]),
+      ("toc", "toc", vec![None]),
+      (
+        "toc  Read   'the  docs'",
+        "toc",
+        vec![Some(Value::Text("Read the  docs".to_string()))],
+      ),
+    ] {
+      let (command, arguments) = parse(line).unwrap().unwrap();
+
+      assert_eq!(command.name, name, "{line:?}");
+      assert_eq!(arguments.0, values, "{line:?}");
+    }
+
+    assert!(parse(" ").unwrap().is_none());
+  }
+
+  #[test]
+  fn rejects_invalid_commands() {
+    for (line, expected) in [
+      (
+        "nope",
+        error(ErrorKind::UnknownCommand("nope".to_string()), 0),
+      ),
+      ("open", error(ErrorKind::MissingArgument("url"), 4)),
+      ("export html", error(ErrorKind::MissingArgument("path"), 11)),
+      (
+        "quit now",
+        error(ErrorKind::UnexpectedArgument("now".to_string()), 5),
+      ),
+      (
+        "wrap -1",
+        error(ErrorKind::InvalidInteger("-1".to_string()), 5),
+      ),
+      (
+        "wrap wide",
+        error(ErrorKind::InvalidInteger("wide".to_string()), 5),
+      ),
+    ] {
+      assert_eq!(parse(line).err(), Some(expected), "{line:?}");
     }
   }
 }
@@ -0,0 +1,175 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+/// Completions for the word starting at `start` of the command line `line`
+pub fn candidates(app: &crate::App, line: &str, start: usize) -> Vec<String> {
+  let word = &line[start..];
+  let mut arguments = line[..start].split_whitespace();
+
+  match arguments.next() {
+    None =>
+      crate::command::COMMANDS
+        .iter()
+        .filter(|command| command.name.starts_with(word))
+        .map(|command| command.name.to_string())
+        .collect(),
+    Some("open" | "o" | "tabnew") if arguments.next().is_none() =>
+      if is_path(word) {
+        paths(word)
+      } else {
+        urls(app, word)
+      },
+    Some("edit" | "e") if arguments.next().is_none() => paths(word),
+    Some("export") =>
+      match arguments.count() {
+        0 =>
+          crate::export::Format::NAMES
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .map(ToString::to_string)
+            .collect(),
+        1 => paths(word),
+        _ => vec![],
+      },
+    _ => vec![],
+  }
+}
+
+fn is_path(word: &str) -> bool {
+  word.starts_with('/') || word.starts_with('.') || word.starts_with('~')
+}
+
+/// Bookmarked and previously visited URLs which start with `word`, with or
+/// without their `gemini://` scheme
+fn urls(app: &crate::App, word: &str) -> Vec<String> {
+  let mut urls: Vec<String> = vec![];
+  let visited = app
+    .capsule_history
+    .iter()
+    .rev()
+    .chain(app.tabs.iter().map(|tab| &tab.url))
+    .map(ToString::to_string);
+
+  for url in crate::bookmarks::load().into_iter().chain(visited) {
+    let matches = url.starts_with(word)
+      || url
+        .strip_prefix("gemini://")
+        .is_some_and(|url| url.starts_with(word));
+
+    if matches && !urls.contains(&url) {
+      urls.push(url);
+    }
+  }
+
+  urls
+}
+
+/// Entries of the directory `word` points into whose names start with the
+/// last component of `word`
+fn paths(word: &str) -> Vec<String> {
+  let (directory, prefix) =
+    word.rfind('/').map_or(("", word), |slash| word.split_at(slash + 1));
+  let expanded = directory.strip_prefix('~').map_or_else(
+    || directory.to_string(),
+    |rest| {
+      crate::config::home()
+        .map(|home| format!("{}{rest}", home.display()))
+        .unwrap_or_default()
+    },
+  );
+  let Ok(entries) =
+    std::fs::read_dir(if expanded.is_empty() { "." } else { &expanded })
+  else {
+    return vec![];
+  };
+  let mut paths = entries
+    .filter_map(Result::ok)
+    .filter_map(|entry| {
+      let name = entry.file_name().to_string_lossy().to_string();
+
+      if !name.starts_with(prefix) || (name.starts_with('.') && prefix.is_empty())
+      {
+        return None;
+      }
+
+      Some(format!(
+        "{directory}{}{}",
+        escape(&name),
+        if entry.path().is_dir() { "/" } else { "" }
+      ))
+    })
+    .collect::<Vec<_>>();
+
+  paths.sort();
+
+  paths
+}
+
+/// Escape the characters of `name` which the command line would otherwise
+/// split arguments at or treat as quotes
+fn escape(name: &str) -> String {
+  let mut escaped = String::with_capacity(name.len());
+
+  for character in name.chars() {
+    if character.is_whitespace() || matches!(character, '"' | '\'' | '\\') {
+      escaped.push('\\');
+    }
+
+    escaped.push(character);
+  }
+
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn escapes_paths() {
+    let directory = std::env::temp_dir()
+      .join(format!("sydney-completion-{}", std::process::id()));
+
+    std::fs::create_dir_all(directory.join("sub dir")).unwrap();
+
+    for name in ["my file.gmi", "it's \"quoted\".gmi", "back\\slash.gmi"] {
+      std::fs::write(directory.join(name), "").unwrap();
+    }
+
+    let word = format!("{}/", directory.display());
+    let paths = paths(&word);
+
+    assert_eq!(
+      paths,
+      [
+        format!(r"{word}back\\slash.gmi"),
+        format!(r#"{word}it\'s\ \"quoted\".gmi"#),
+        format!(r"{word}my\ file.gmi"),
+        format!(r"{word}sub\ dir/"),
+      ]
+    );
+
+    for path in &paths {
+      let tokens = crate::command::tokenize(&format!("open {path}")).unwrap();
+
+      assert_eq!(tokens.len(), 2, "{path:?}");
+      assert!(std::path::Path::new(&tokens[1].text).exists(), "{path:?}");
+    }
+
+    std::fs::remove_dir_all(&directory).unwrap();
+  }
+}
@@ -0,0 +1,343 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sydney's configuration file, a list of `key = value` pairs where lines
+//! starting with `#` are comments
+//!
+//! ```text
+//! # Pipe yanked text to an external program instead of using OSC 52
+//! clipboard_command = wl-copy
+//! # Leave the mouse to the terminal so that text can be selected natively
+//! mouse = false
+//! # A URL, about page or path to a local Gemtext file to start at
+//! homepage = about:blank
+//! # Reopen the tabs of the last session instead of the homepage
+//! startup = session
+//! # Check for newer versions of Sydney at most once a day
+//! update_check = true
+//! # One of dark, light or terminal
+//! theme = light
+//! # Save images, archives and other pages which cannot be displayed here
+//! download_directory = ~/Downloads/gemini
+//! # Send Misfin mail from the mailbox of the certificate in
+//! # identities/mail.pem
+//! misfin_identity = mail
+//!
+//! # Present the client certificate in identities/work.pem to these hosts,
+//! # and no certificate to any other
+//! [identities]
+//! example.com = work
+//! ```
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::theme::Theme;
+
+/// What Sydney opens when it is started without a URL
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Startup {
+  /// The homepage
+  Homepage,
+  /// The tabs which were open when Sydney last quit
+  Session,
+}
+
+/// The configuration of Sydney, see the module documentation for its keys
+#[derive(Clone)]
+pub struct Config {
+  /// The program yanked text is piped to, instead of using OSC 52
+  pub clipboard_command:  Option<String>,
+  /// Whether the interface captures the mouse
+  pub mouse:              bool,
+  /// A URL, about page or path to a local Gemtext file to start at
+  pub homepage:           String,
+  /// What is opened when Sydney is started without a URL
+  pub startup:            Startup,
+  /// Whether to check for newer versions of Sydney at most once a day
+  pub update_check:       bool,
+  /// The colours the interface is painted with
+  pub theme:              Theme,
+  /// The name of the identity to present to each host, keyed by the
+  /// lowercase host
+  pub identities:         BTreeMap<String, String>,
+  /// Where pages which cannot be displayed are saved
+  pub download_directory: Option<String>,
+  /// The name of the identity Misfin mail is sent from, whose certificate
+  /// is the sender's mailbox
+  pub misfin_identity:    Option<String>,
+}
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      clipboard_command:  None,
+      mouse:              true,
+      homepage:           "about:help".to_string(),
+      startup:            Startup::Homepage,
+      update_check:       false,
+      theme:              Theme::Dark,
+      identities:         BTreeMap::new(),
+      download_directory: None,
+      misfin_identity:    None,
+    }
+  }
+}
+impl Config {
+  /// Load the configuration file from the default location, falling back to
+  /// the default configuration if it does not exist
+  ///
+  /// # Errors
+  ///
+  /// If the configuration file could not be read or parsed.
+  pub fn load() -> Result<Self, String> {
+    match path() {
+      Some(path) if path.exists() => Self::load_from(&path),
+      _ => Ok(Self::default()),
+    }
+  }
+
+  /// The name of the identity to present to `host`, if any
+  #[must_use]
+  pub fn identity(&self, host: &str) -> Option<&str> {
+    self
+      .identities
+      .get(&host.to_lowercase())
+      .map(String::as_str)
+  }
+
+  /// Every configuration key alongside its current value
+  #[must_use]
+  pub fn entries(&self) -> Vec<(&'static str, String)> {
+    vec![
+      (
+        "clipboard_command",
+        self.clipboard_command.clone().unwrap_or_default(),
+      ),
+      ("mouse", self.mouse.to_string()),
+      ("homepage", self.homepage.clone()),
+      (
+        "startup",
+        match self.startup {
+          Startup::Homepage => "homepage",
+          Startup::Session => "session",
+        }
+        .to_string(),
+      ),
+      ("update_check", self.update_check.to_string()),
+      ("theme", self.theme.name().to_string()),
+      (
+        "download_directory",
+        self.download_directory.clone().unwrap_or_default(),
+      ),
+      (
+        "misfin_identity",
+        self.misfin_identity.clone().unwrap_or_default(),
+      ),
+    ]
+  }
+
+  /// Load the configuration file at `path`
+  ///
+  /// # Errors
+  ///
+  /// If the file could not be read or parsed.
+  pub fn load_from(path: &std::path::Path) -> Result<Self, String> {
+    let content = std::fs::read_to_string(path).map_err(|error| {
+      format!("Could not read configuration {}: {error}", path.display())
+    })?;
+
+    Self::parse(&content)
+  }
+
+  /// Parse the content of a configuration file
+  ///
+  /// # Errors
+  ///
+  /// If a line is not a `key = value` pair, or a key or value is unknown.
+  pub fn parse(content: &str) -> Result<Self, String> {
+    let mut config = Self::default();
+    let mut identities = false;
+
+    for (number, line) in content.lines().enumerate() {
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      if let Some(section) = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+      {
+        if section.trim() != "identities" {
+          return Err(format!(
+            "Unknown configuration section \"{section}\" on line {}",
+            number + 1
+          ));
+        }
+
+        identities = true;
+
+        continue;
+      }
+
+      let Some((key, value)) = line.split_once('=') else {
+        return Err(format!(
+          "Configuration line {} is not a \"key = value\" pair",
+          number + 1
+        ));
+      };
+      let value = unquote(value.trim());
+
+      if identities {
+        config
+          .identities
+          .insert(unquote(key.trim()).to_lowercase(), value);
+
+        continue;
+      }
+
+      match key.trim() {
+        "clipboard_command" =>
+          config.clipboard_command =
+            if value.is_empty() { None } else { Some(value) },
+        "mouse" => config.mouse = parse_bool(&value, number)?,
+        "homepage" => config.homepage = value,
+        "update_check" => config.update_check = parse_bool(&value, number)?,
+        "theme" =>
+          config.theme = Theme::parse(&value)
+            .map_err(|error| format!("{error} on line {}", number + 1))?,
+        "download_directory" =>
+          config.download_directory =
+            if value.is_empty() { None } else { Some(value) },
+        "misfin_identity" =>
+          config.misfin_identity =
+            if value.is_empty() { None } else { Some(value) },
+        "startup" =>
+          config.startup = match value.as_str() {
+            "homepage" => Startup::Homepage,
+            "session" => Startup::Session,
+            _ =>
+              return Err(format!(
+                "Expected homepage or session but found \"{value}\" on line {}",
+                number + 1
+              )),
+          },
+        key =>
+          return Err(format!(
+            "Unknown configuration key \"{key}\" on line {}",
+            number + 1
+          )),
+      }
+    }
+
+    Ok(config)
+  }
+}
+
+/// The directory Sydney reads its configuration from
+pub fn directory() -> Option<PathBuf> {
+  std::env::var_os("XDG_CONFIG_HOME")
+    .filter(|directory| !directory.is_empty())
+    .map(PathBuf::from)
+    .or_else(|| home().map(|home| home.join(".config")))
+    .map(|directory| directory.join("sydney"))
+}
+
+/// The directory Sydney keeps its data, like bookmarks, in
+pub fn data_directory() -> Option<PathBuf> {
+  std::env::var_os("XDG_DATA_HOME")
+    .filter(|directory| !directory.is_empty())
+    .map(PathBuf::from)
+    .or_else(|| home().map(|home| home.join(".local").join("share")))
+    .map(|directory| directory.join("sydney"))
+}
+
+/// The directory Sydney caches data which can be regenerated in
+pub fn cache_directory() -> Option<PathBuf> {
+  std::env::var_os("XDG_CACHE_HOME")
+    .filter(|directory| !directory.is_empty())
+    .map(PathBuf::from)
+    .or_else(|| home().map(|home| home.join(".cache")))
+    .map(|directory| directory.join("sydney"))
+}
+
+/// The path of the configuration file
+#[must_use]
+pub fn path() -> Option<PathBuf> {
+  directory().map(|directory| directory.join("config"))
+}
+
+/// The home directory of the current user
+pub fn home() -> Option<PathBuf> {
+  std::env::var_os("HOME")
+    .or_else(|| std::env::var_os("USERPROFILE"))
+    .filter(|home| !home.is_empty())
+    .map(PathBuf::from)
+}
+
+fn parse_bool(value: &str, number: usize) -> Result<bool, String> {
+  match value {
+    "true" | "yes" | "on" => Ok(true),
+    "false" | "no" | "off" => Ok(false),
+    _ =>
+      Err(format!(
+        "Expected true or false but found \"{value}\" on line {}",
+        number + 1
+      )),
+  }
+}
+
+fn unquote(value: &str) -> String {
+  value
+    .strip_prefix('"')
+    .and_then(|value| value.strip_suffix('"'))
+    .unwrap_or(value)
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn maps_identities_to_hosts() {
+    let config = Config::parse(
+      "misfin_identity = mail\n[identities]\nExample.com = \
+       work\n\"mail.example.com\" = mail\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.misfin_identity.as_deref(), Some("mail"));
+    assert_eq!(config.identity("example.com"), Some("work"));
+    assert_eq!(config.identity("EXAMPLE.COM"), Some("work"));
+    assert_eq!(config.identity("mail.example.com"), Some("mail"));
+    assert_eq!(config.identity("other.example.com"), None);
+  }
+
+  #[test]
+  fn rejects_invalid_configurations() {
+    for content in [
+      "identity = work",
+      "[hosts]",
+      "mouse = maybe",
+      "homepage",
+      "colour = red",
+    ] {
+      assert!(Config::parse(content).is_err(), "{content:?}");
+    }
+  }
+}
@@ -0,0 +1,176 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Gemtext documents as the list of items Sydney displays
+
+use germ::ast::Node;
+
+/// A line of a document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+  /// What the line holds
+  pub nodes:        Vec<Node>,
+  /// The target of the line, if it is a link
+  pub link:         Option<String>,
+  /// Whether the line is inside of a preformatted block
+  pub preformatted: bool,
+  /// Whether the line is a Spartan `=:` prompt line, a link which asks for
+  /// input to upload to its target
+  pub prompt:       bool,
+}
+impl Item {
+  /// A line holding `node` which is not a link
+  #[must_use]
+  pub fn new(node: Node) -> Self {
+    Self {
+      nodes:        vec![node],
+      link:         None,
+      preformatted: false,
+      prompt:       false,
+    }
+  }
+}
+
+/// Split a plain text document into one item per line
+#[must_use]
+pub fn plain(content: &str) -> Vec<Item> {
+  content
+    .lines()
+    .map(|line| {
+      let node = if line.trim().is_empty() {
+        Node::Whitespace
+      } else {
+        Node::Text(line.replace('\t', "    "))
+      };
+
+      Item::new(node)
+    })
+    .collect()
+}
+
+/// Parse a Gemtext document into one item per line
+#[must_use]
+pub fn parse(content: &str) -> Vec<Item> {
+  let mut items: Vec<Item> = vec![];
+  let mut pre = false;
+
+  for line in content.lines() {
+    let line = line.replace('\t', " ");
+    let pre_like = if line.starts_with("```") {
+      pre = !pre;
+
+      true
+    } else {
+      false
+    };
+
+    let ast = germ::ast::Ast::from_string(&line);
+    let ast_node = ast.inner().first().map_or_else(
+      || {
+        if pre_like || pre {
+          if line == "```" {
+            Node::Text("sydney_abc_123".to_string())
+          } else {
+            Node::Text(line.get(3..).unwrap_or("").to_string())
+          }
+        } else {
+          Node::Whitespace
+        }
+      },
+      |node| normalize_heading(node.clone()),
+    );
+
+    let mut parts = line.split_whitespace();
+
+    if let (false, Some(prompt)) = (pre, line.strip_prefix("=:")) {
+      items.push(self::prompt(prompt));
+    } else if let (Some("=>"), Some(to)) = (parts.next(), parts.next()) {
+      items.push(Item {
+        link: Some(to.to_string()),
+        ..Item::new(ast_node)
+      });
+    } else {
+      items.push(Item {
+        preformatted: pre,
+        ..Item::new(ast_node)
+      });
+    }
+  }
+
+  items
+}
+
+/// Append `text` to the Gemtext document `gemtext` as a line of text
+///
+/// Text which happens to look like Gemtext is indented by a space, so that
+/// it is not parsed as such.
+pub fn push_text(gemtext: &mut String, text: &str) {
+  if ["#", "*", ">", "```", "=>", "=:"]
+    .iter()
+    .any(|marker| text.starts_with(marker))
+  {
+    gemtext.push(' ');
+  }
+
+  gemtext.push_str(text);
+  gemtext.push('\n');
+}
+
+/// Turn what germ parses as a heading into one Gemtext allows
+///
+/// germ parses lines like `#hashtag` as headings of level zero, which are
+/// text, and keeps the depth of headings deeper than Gemtext's three levels,
+/// which are clamped to the third.
+#[must_use]
+pub fn normalize_heading(node: Node) -> Node {
+  match node {
+    Node::Heading {
+      level: 0,
+      text,
+    } => Node::Text(text),
+    Node::Heading {
+      level,
+      text,
+    } =>
+      Node::Heading {
+        level: level.min(3),
+        text,
+      },
+    node => node,
+  }
+}
+
+/// A Spartan `=:` prompt line, which Gemtext parsers see as text, from what
+/// follows its `=:`
+fn prompt(line: &str) -> Item {
+  let line = line.trim();
+  let (to, text) = line
+    .split_once(char::is_whitespace)
+    .map_or((line, None), |(to, text)| {
+      (to, Some(text.trim().to_string()))
+    });
+
+  Item {
+    nodes:        vec![Node::Link {
+      to: to.to_string(),
+      text,
+    }],
+    link:         Some(to.to_string()),
+    preformatted: false,
+    prompt:       true,
+  }
+}
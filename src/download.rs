@@ -0,0 +1,80 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Saving responses which cannot be displayed, like images and archives
+
+use std::path::PathBuf;
+
+use url::Url;
+
+use crate::config::Config;
+
+/// The directory downloads are saved to, the `download_directory`
+/// configuration key, `$XDG_DOWNLOAD_DIR` or `~/Downloads`
+pub fn directory(config: &Config) -> Result<PathBuf, String> {
+  if let Some(directory) = &config.download_directory {
+    return crate::url::expand_path(directory);
+  }
+
+  std::env::var_os("XDG_DOWNLOAD_DIR")
+    .filter(|directory| !directory.is_empty())
+    .map(PathBuf::from)
+    .or_else(|| crate::config::home().map(|home| home.join("Downloads")))
+    .ok_or_else(|| "Could not locate the download directory".to_string())
+}
+
+/// Save `body`, fetched from `url`, to the download directory without
+/// overwriting any existing file, returning where it was saved
+pub fn save(
+  url: &Url,
+  body: &[u8],
+  config: &Config,
+) -> Result<PathBuf, String> {
+  let directory = directory(config)?;
+  let name = file_name(url);
+  let (stem, extension) = match name.rsplit_once('.') {
+    Some((stem, extension)) if !stem.is_empty() =>
+      (stem.to_string(), format!(".{extension}")),
+    _ => (name.clone(), String::new()),
+  };
+  let mut path = directory.join(&name);
+  let mut copy = 1;
+
+  while path.exists() {
+    path = directory.join(format!("{stem} ({copy}){extension}"));
+    copy += 1;
+  }
+
+  std::fs::create_dir_all(&directory)
+    .and_then(|()| std::fs::write(&path, body))
+    .map_err(|error| format!("Could not save {}: {error}", path.display()))?;
+
+  Ok(path)
+}
+
+/// The last segment of the path of `url`, or `download` if it has none
+fn file_name(url: &Url) -> String {
+  url
+    .path_segments()
+    .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+    .map(|segment| {
+      String::from_utf8_lossy(&crate::url::percent_decode(segment))
+        .replace(['/', '\\', '\0'], "_")
+    })
+    .filter(|name| !name.starts_with('.'))
+    .unwrap_or_else(|| "download".to_string())
+}
@@ -0,0 +1,198 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! `sydney fetch`, which prints a page to standard output instead of opening
+//! the interface
+
+use std::io::{IsTerminal, Write};
+
+use crossterm::style::{Attribute, Stylize};
+use ratatui::{style::Modifier, text::Span};
+use url::Url;
+
+use crate::{
+  config::Config,
+  export::{export, Format},
+  request::Response,
+  transport::Registry,
+};
+
+/// How `sydney fetch` prints a page
+pub struct Options {
+  /// A URL, a path or `-` for standard input
+  pub location: String,
+  /// Print the body exactly as it was received
+  pub raw:      bool,
+  /// Print the response header before the body
+  pub header:   bool,
+  /// Style the page with ANSI escape sequences
+  pub color:    bool,
+  /// Convert the page to a format instead of rendering it
+  pub format:   Option<Format>,
+  /// The width to wrap text at, the width of the terminal or 80 columns if
+  /// not set
+  pub wrap_at:  Option<u16>,
+  /// The URL links are relative to when the page is read from standard input
+  pub base:     Option<Url>,
+}
+
+/// Fetch and print a page, returning the exit status
+///
+/// The exit status is zero for successful (2x) responses, the Gemini status
+/// code for any other response, and one if the page could not be fetched at
+/// all.
+///
+/// # Panics
+///
+/// Never, the URL of standard input is always a valid URL.
+#[must_use]
+pub fn main(options: &Options, config: &Config) -> i32 {
+  let response = if options.location == "-" {
+    crate::stdin::read().map(|content| {
+      (
+        options
+          .base
+          .clone()
+          .unwrap_or_else(|| Url::parse(crate::stdin::URL).unwrap()),
+        Response::success("text/gemini", content),
+      )
+    })
+  } else {
+    crate::url::parse_location(&options.location)
+      .and_then(|url| Registry::default().fetch(&url, config))
+  };
+
+  match response {
+    Ok((url, response)) => {
+      if let Err(error) = write(&url, &response, options, config) {
+        if error.kind() != std::io::ErrorKind::BrokenPipe {
+          eprintln!("sydney: {error}");
+
+          return 1;
+        }
+      }
+
+      if response.is_success() {
+        0
+      } else {
+        if !options.header {
+          eprintln!("sydney: {} {}", response.status, response.meta);
+        }
+
+        i32::from(response.status)
+      }
+    }
+    Err(error) => {
+      eprintln!("sydney: {error}");
+
+      1
+    }
+  }
+}
+
+fn write(
+  url: &Url,
+  response: &Response,
+  options: &Options,
+  config: &Config,
+) -> std::io::Result<()> {
+  let mut stdout = std::io::stdout().lock();
+
+  if options.header {
+    write!(stdout, "{} {}\r\n", response.status, response.meta)?;
+  }
+
+  if !response.is_success() {
+    return stdout.flush();
+  }
+
+  if options.raw {
+    stdout.write_all(&response.body)?;
+
+    return stdout.flush();
+  }
+
+  if let Some(format) = options.format {
+    stdout.write_all(export(&response.text(), url, format).as_bytes())?;
+
+    return stdout.flush();
+  }
+
+  let wrap_at = options.wrap_at.unwrap_or_else(|| {
+    if std::io::stdout().is_terminal() {
+      crossterm::terminal::size().map_or(80, |(columns, _)| columns)
+    } else {
+      80
+    }
+  });
+  let palette = config.theme.palette();
+
+  if !response.is_text() {
+    stdout.write_all(&response.body)?;
+
+    return stdout.flush();
+  }
+
+  let items = if response.mime() == "text/gemini" {
+    crate::document::parse(&response.text())
+  } else {
+    crate::document::plain(&response.text())
+  };
+
+  for line in crate::ui::render(&items, wrap_at, &palette).lines {
+    let text = line
+      .spans
+      .iter()
+      .map(|span| {
+        if options.color {
+          styled(span)
+        } else {
+          span.content.to_string()
+        }
+      })
+      .collect::<String>();
+
+    writeln!(stdout, "{}", text.trim_end_matches('\n'))?;
+  }
+
+  stdout.flush()
+}
+
+/// A span with its style applied through ANSI escape sequences
+fn styled(span: &Span<'_>) -> String {
+  let mut content = span.content.as_ref().stylize();
+
+  if let Some(color) = span.style.fg {
+    content = content.with(color.into());
+  }
+
+  if let Some(color) = span.style.bg {
+    content = content.on(color.into());
+  }
+
+  for (modifier, attribute) in [
+    (Modifier::BOLD, Attribute::Bold),
+    (Modifier::ITALIC, Attribute::Italic),
+    (Modifier::UNDERLINED, Attribute::Underlined),
+  ] {
+    if span.style.add_modifier.contains(modifier) {
+      content = content.attribute(attribute);
+    }
+  }
+
+  content.to_string()
+}
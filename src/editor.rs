@@ -0,0 +1,141 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Editing text in an external editor while the interface is suspended
+
+use std::{
+  fs::{File, OpenOptions},
+  hash::{BuildHasher, Hasher},
+  io::Write,
+  path::PathBuf,
+};
+
+use crossterm::{event, execute, terminal};
+
+/// Edit `content` in `$VISUAL`, `$EDITOR` or `vi`, returning the edited text
+///
+/// The text is edited in a temporary file with the given extension, so that
+/// editors can pick a suitable syntax. `mouse` is whether the interface
+/// captures the mouse, which is released while the editor runs.
+pub fn edit(
+  content: &str,
+  extension: &str,
+  mouse: bool,
+) -> Result<String, String> {
+  let (path, mut file) = create(extension)?;
+
+  file
+    .write_all(content.as_bytes())
+    .map_err(|error| format!("Could not write {}: {error}", path.display()))?;
+  drop(file);
+
+  let result = suspend(mouse, || run(&path));
+  let edited = result.and_then(|()| {
+    std::fs::read_to_string(&path)
+      .map_err(|error| format!("Could not read {}: {error}", path.display()))
+  });
+
+  let _ = std::fs::remove_file(&path);
+
+  edited
+}
+
+/// Create a new temporary file with an unpredictable name which only the
+/// current user can read, never opening a file or link which already exists
+fn create(extension: &str) -> Result<(PathBuf, File), String> {
+  let mut last_error = None;
+
+  for _ in 0..16 {
+    let mut hasher =
+      std::collections::hash_map::RandomState::new().build_hasher();
+
+    hasher.write_u32(std::process::id());
+
+    let path = std::env::temp_dir()
+      .join(format!("sydney-{:016x}.{extension}", hasher.finish()));
+    let mut options = OpenOptions::new();
+
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    match options.open(&path) {
+      Ok(file) => return Ok((path, file)),
+      Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists =>
+        last_error = Some(error),
+      Err(error) =>
+        return Err(format!("Could not create {}: {error}", path.display())),
+    }
+  }
+
+  Err(format!(
+    "Could not create a temporary file: {}",
+    last_error.map_or_else(String::new, |error| error.to_string())
+  ))
+}
+
+fn run(path: &std::path::Path) -> Result<(), String> {
+  let command = ["VISUAL", "EDITOR"]
+    .into_iter()
+    .filter_map(std::env::var_os)
+    .map(|command| command.to_string_lossy().into_owned())
+    .find(|command| !command.trim().is_empty())
+    .unwrap_or_else(|| "vi".to_string());
+  let mut arguments = command.split_whitespace();
+  let program = arguments.next().unwrap_or("vi");
+  let status = std::process::Command::new(program)
+    .args(arguments)
+    .arg(path)
+    .status()
+    .map_err(|error| format!("Could not run \"{program}\": {error}"))?;
+
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("\"{program}\" exited with {status}"))
+  }
+}
+
+/// Hand the terminal back to the shell while `f` runs
+fn suspend<T>(
+  mouse: bool,
+  f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+  let mut stdout = std::io::stdout();
+  let leave = terminal::disable_raw_mode()
+    .and_then(|()| execute!(stdout, terminal::LeaveAlternateScreen))
+    .and_then(|()| {
+      if mouse {
+        execute!(stdout, event::DisableMouseCapture)
+      } else {
+        Ok(())
+      }
+    });
+  let result = leave.map_err(|error| error.to_string()).and_then(|()| f());
+  let enter = execute!(stdout, terminal::EnterAlternateScreen)
+    .and_then(|()| terminal::enable_raw_mode())
+    .and_then(|()| {
+      if mouse {
+        execute!(stdout, event::EnableMouseCapture)
+      } else {
+        Ok(())
+      }
+    });
+
+  enter.map_err(|error| error.to_string()).and(result)
+}
@@ -0,0 +1,386 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Conversion of Gemtext documents to other formats
+
+use std::fmt::Write;
+
+use germ::ast::Node;
+use url::Url;
+
+/// The schemes of links which are kept as links
+const LINKABLE_SCHEMES: &[&str] = &[
+  "gemini", "gopher", "http", "https", "spartan", "nex", "finger", "mailto",
+  "misfin",
+];
+const STYLESHEET: &str = "
+body { max-width: 40em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+pre { overflow-x: auto; padding: 1em; background: #f4f4f4; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 3px solid #ccc; }
+";
+
+/// A format Gemtext documents can be exported to
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+  /// Markdown, escaping what Gemtext does not mark up
+  Markdown,
+  /// A standalone HTML page
+  Html,
+  /// Plain text without Gemtext line markers
+  Text,
+}
+impl Format {
+  /// The name of every format, as accepted by [`Format::parse`]
+  pub const NAMES: &'static [&'static str] = &["html", "markdown", "text"];
+
+  /// The format called `name`, or one of its aliases
+  ///
+  /// # Errors
+  ///
+  /// If `name` is not a known format.
+  pub fn parse(name: &str) -> Result<Self, String> {
+    match name {
+      "markdown" | "md" => Ok(Self::Markdown),
+      "html" => Ok(Self::Html),
+      "text" | "txt" => Ok(Self::Text),
+      _ =>
+        Err(format!(
+          "\"{name}\" is not a valid format, expected one of {}",
+          Self::NAMES.join(", ")
+        )),
+    }
+  }
+}
+
+/// Convert the Gemtext document `source`, fetched from `base`, to `format`
+///
+/// Relative links are resolved against `base` so that the exported document
+/// still works once it has been moved elsewhere.
+#[must_use]
+pub fn export(source: &str, base: &Url, format: Format) -> String {
+  let nodes = germ::ast::Ast::from_string(source)
+    .inner()
+    .iter()
+    .cloned()
+    .map(crate::document::normalize_heading)
+    .collect::<Vec<_>>();
+
+  match format {
+    Format::Markdown => markdown(&nodes, base),
+    Format::Html => html(&nodes, base),
+    Format::Text => text(&nodes, base),
+  }
+}
+
+fn absolute(base: &Url, to: &str) -> String {
+  base.join(to).map_or_else(|_| to.to_string(), String::from)
+}
+
+/// Whether a link to `to` is safe to keep as a link, anything else, like
+/// `javascript:` or `data:` URLs, is written out as text
+fn linkable(to: &str) -> bool {
+  Url::parse(to).is_ok_and(|url| LINKABLE_SCHEMES.contains(&url.scheme()))
+}
+
+/// A link which is not [`linkable`], as text
+fn unlinked(to: &str, label: Option<&str>) -> String {
+  label.map_or_else(|| to.to_string(), |label| format!("{label} <{to}>"))
+}
+
+fn markdown(nodes: &[Node], base: &Url) -> String {
+  let mut blocks = vec![];
+
+  for node in nodes {
+    blocks.push(match node {
+      Node::Text(text) => escape_markdown(text),
+      Node::Link {
+        to,
+        text,
+      } => {
+        let to = absolute(base, to);
+
+        if linkable(&to) {
+          format!(
+            "[{}]({})",
+            escape_markdown(text.as_deref().unwrap_or(&to)),
+            to.replace('(', "%28").replace(')', "%29")
+          )
+        } else {
+          escape_markdown(&unlinked(&to, text.as_deref()))
+        }
+      }
+      Node::Heading {
+        level,
+        text,
+      } => format!("{} {}", "#".repeat(*level), escape_markdown(text)),
+      Node::List(items) =>
+        items
+          .iter()
+          .map(|item| format!("* {}", escape_markdown(item)))
+          .collect::<Vec<_>>()
+          .join("\n"),
+      Node::Blockquote(text) => format!("> {}", escape_markdown(text)),
+      Node::PreformattedText {
+        alt_text,
+        text,
+      } => {
+        let fence = if text.contains("```") { "~~~" } else { "```" };
+
+        format!(
+          "{fence}{}\n{}\n{fence}",
+          alt_text.as_deref().unwrap_or_default(),
+          text.trim_end_matches('\n')
+        )
+      }
+      Node::Whitespace => continue,
+    });
+  }
+
+  blocks.join("\n\n") + "\n"
+}
+
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+
+  for character in text.chars() {
+    if "\\`*_[]<>#|~".contains(character) {
+      escaped.push('\\');
+    }
+
+    escaped.push(character);
+  }
+
+  escaped
+}
+
+fn html(nodes: &[Node], base: &Url) -> String {
+  let title = nodes
+    .iter()
+    .find_map(|node| {
+      match node {
+        Node::Heading {
+          text, ..
+        } => Some(text.clone()),
+        _ => None,
+      }
+    })
+    .unwrap_or_else(|| base.to_string());
+  let mut body = String::new();
+
+  for node in nodes {
+    let _ = match node {
+      Node::Text(text) => writeln!(body, "<p>{}</p>", escape_html(text)),
+      Node::Link {
+        to,
+        text,
+      } => {
+        let to = absolute(base, to);
+
+        if linkable(&to) {
+          writeln!(
+            body,
+            "<p><a href=\"{}\">{}</a></p>",
+            escape_html(&to),
+            escape_html(text.as_deref().unwrap_or(&to))
+          )
+        } else {
+          writeln!(
+            body,
+            "<p>{}</p>",
+            escape_html(&unlinked(&to, text.as_deref()))
+          )
+        }
+      }
+      Node::Heading {
+        level,
+        text,
+      } => writeln!(body, "<h{level}>{}</h{level}>", escape_html(text)),
+      Node::List(items) => {
+        body.push_str("<ul>\n");
+
+        for item in items {
+          let _ = writeln!(body, "<li>{}</li>", escape_html(item));
+        }
+
+        writeln!(body, "</ul>")
+      }
+      Node::Blockquote(text) =>
+        writeln!(body, "<blockquote>{}</blockquote>", escape_html(text)),
+      Node::PreformattedText {
+        alt_text,
+        text,
+      } =>
+        writeln!(
+          body,
+          "<pre{}>{}</pre>",
+          alt_text.as_ref().map_or_else(String::new, |alt_text| {
+            format!(" aria-label=\"{}\"", escape_html(alt_text))
+          }),
+          escape_html(text.trim_end_matches('\n'))
+        ),
+      Node::Whitespace => Ok(()),
+    };
+  }
+
+  format!(
+    "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+<style>{STYLESHEET}</style>
+</head>
+<body>
+{body}</body>
+</html>
+",
+    escape_html(&title)
+  )
+}
+
+fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+
+  for character in text.chars() {
+    match character {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(character),
+    }
+  }
+
+  escaped
+}
+
+fn text(nodes: &[Node], base: &Url) -> String {
+  let mut text = String::new();
+
+  for node in nodes {
+    let _ = match node {
+      Node::Text(line)
+      | Node::Heading {
+        text: line, ..
+      } => writeln!(text, "{line}"),
+      Node::Link {
+        to,
+        text: label,
+      } => {
+        let to = absolute(base, to);
+
+        match label {
+          Some(label) => writeln!(text, "{label} <{to}>"),
+          None => writeln!(text, "{to}"),
+        }
+      }
+      Node::List(items) => {
+        for item in items {
+          let _ = writeln!(text, "* {item}");
+        }
+
+        Ok(())
+      }
+      Node::Blockquote(line) => writeln!(text, "> {line}"),
+      Node::PreformattedText {
+        text: block, ..
+      } => writeln!(text, "{}", block.trim_end_matches('\n')),
+      Node::Whitespace => writeln!(text),
+    };
+  }
+
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn export(source: &str, format: Format) -> String {
+    super::export(
+      source,
+      &Url::parse("gemini://fuwn.me/log/").unwrap(),
+      format,
+    )
+  }
+
+  #[test]
+  fn keeps_links_to_known_schemes() {
+    for (line, html, markdown) in [
+      (
+        "=> entry.gmi Entry",
+        "<a href=\"gemini://fuwn.me/log/entry.gmi\">Entry</a>",
+        "[Entry](gemini://fuwn.me/log/entry.gmi)",
+      ),
+      (
+        "=> https://example.com",
+        "<a href=\"https://example.com/\">https://example.com/</a>",
+        "[https://example.com/](https://example.com/)",
+      ),
+      (
+        "=> mailto:fuwn@fuwn.me Mail",
+        "<a href=\"mailto:fuwn@fuwn.me\">Mail</a>",
+        "[Mail](mailto:fuwn@fuwn.me)",
+      ),
+    ] {
+      assert!(export(line, Format::Html).contains(html), "{line}");
+      assert!(export(line, Format::Markdown).contains(markdown), "{line}");
+    }
+  }
+
+  #[test]
+  fn writes_links_to_other_schemes_as_text() {
+    for (line, html, markdown) in [
+      (
+        "=> javascript:alert(document.cookie) x",
+        "<p>x &lt;javascript:alert(document.cookie)&gt;</p>",
+        "x \\<javascript:alert(document.cookie)\\>",
+      ),
+      (
+        "=> data:text/html,<script>alert(1)</script>",
+        "<p>data:text/html,&lt;script&gt;alert(1)&lt;/script&gt;</p>",
+        "data:text/html,\\<script\\>alert(1)\\</script\\>",
+      ),
+      (
+        "=> JavaScript:alert(1) x",
+        "<p>x &lt;javascript:alert(1)&gt;</p>",
+        "x \\<javascript:alert(1)\\>",
+      ),
+    ] {
+      let exported = export(line, Format::Html);
+
+      assert!(exported.contains(html), "{line}\n{exported}");
+      assert!(!exported.contains("<a "), "{line}\n{exported}");
+      assert_eq!(export(line, Format::Markdown), format!("{markdown}\n"));
+    }
+  }
+
+  #[test]
+  fn clamps_heading_levels() {
+    for (line, html, markdown) in [
+      ("# One", "<h1>One</h1>", "# One\n"),
+      ("### Three", "<h3>Three</h3>", "### Three\n"),
+      ("#### Four", "<h3>Four</h3>", "### Four\n"),
+      ("#tag", "<p>#tag</p>", "\\#tag\n"),
+    ] {
+      assert!(export(line, Format::Html).contains(html), "{line}");
+      assert_eq!(export(line, Format::Markdown), markdown, "{line}");
+    }
+  }
+}
@@ -0,0 +1,55 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A Finger client
+//!
+//! The user to look up is taken from either form of Finger URL,
+//! `finger://user@host` or `finger://host/user`.
+
+use std::io::{Read, Write};
+
+use url::Url;
+
+use crate::request::Response;
+
+const DEFAULT_PORT: u16 = 79;
+
+/// Request `url`, displaying the response as plain text
+pub fn request(url: &Url) -> Result<Response, String> {
+  let host = url.host_str().ok_or_else(|| format!("{url} has no host"))?;
+  let user = if url.username().is_empty() {
+    crate::url::percent_decode(url.path().trim_start_matches('/'))
+  } else {
+    crate::url::percent_decode(url.username())
+  };
+  let mut stream =
+    crate::request::connect(host, url.port().unwrap_or(DEFAULT_PORT))?;
+  let mut query = user;
+  let mut body = vec![];
+
+  query.extend(b"\r\n");
+
+  stream
+    .write_all(&query)
+    .and_then(|()| stream.read_to_end(&mut body))
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  Ok(Response::success(
+    "text/plain",
+    String::from_utf8_lossy(&body).replace("\r\n", "\n"),
+  ))
+}
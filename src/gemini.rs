@@ -0,0 +1,120 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A minimal Gemini client
+//!
+//! Gemini capsules overwhelmingly use self-signed certificates, so every
+//! server certificate is accepted.
+
+use std::{
+  io::{Read, Write},
+  sync::Arc,
+  time::SystemTime,
+};
+
+use rustls::{
+  client::{ServerCertVerified, ServerCertVerifier},
+  Certificate,
+  ClientConfig,
+  ClientConnection,
+  ServerName,
+  StreamOwned,
+};
+use url::Url;
+
+use crate::{identity::Identity, request::Response};
+
+pub const DEFAULT_PORT: u16 = 1965;
+
+struct AcceptAll;
+impl ServerCertVerifier for AcceptAll {
+  fn verify_server_cert(
+    &self,
+    _end_entity: &Certificate,
+    _intermediates: &[Certificate],
+    _server_name: &ServerName,
+    _scts: &mut dyn Iterator<Item = &[u8]>,
+    _ocsp_response: &[u8],
+    _now: SystemTime,
+  ) -> Result<ServerCertVerified, rustls::Error> {
+    Ok(ServerCertVerified::assertion())
+  }
+}
+
+/// Request `url` from its Gemini server, presenting `identity` as the client
+/// certificate if given
+pub fn request(
+  url: &Url,
+  identity: Option<&Identity>,
+) -> Result<Response, String> {
+  exchange(
+    url,
+    url.port().unwrap_or(DEFAULT_PORT),
+    format!("{url}\r\n").as_bytes(),
+    identity,
+  )
+}
+
+/// Send `request` to the server of `url` on `port` over TLS and read back its
+/// Gemini-style response, which Titan and Misfin share
+pub fn exchange(
+  url: &Url,
+  port: u16,
+  request: &[u8],
+  identity: Option<&Identity>,
+) -> Result<Response, String> {
+  let host = url
+    .host_str()
+    .ok_or_else(|| format!("{url} has no host"))?
+    .trim_start_matches('[')
+    .trim_end_matches(']');
+  let builder = ClientConfig::builder()
+    .with_safe_defaults()
+    .with_custom_certificate_verifier(Arc::new(AcceptAll));
+  let config = match identity {
+    Some(identity) =>
+      builder
+        .with_client_auth_cert(
+          identity.certificates.clone(),
+          identity.key.clone(),
+        )
+        .map_err(|error| format!("Could not use the identity: {error}"))?,
+    None => builder.with_no_client_auth(),
+  };
+  let server_name = ServerName::try_from(host)
+    .map_err(|error| format!("{host} is not a valid server name: {error}"))?;
+  let connection = ClientConnection::new(Arc::new(config), server_name)
+    .map_err(|error| error.to_string())?;
+  let stream = crate::request::connect(host, port)?;
+  let mut tls = StreamOwned::new(connection, stream);
+  let mut data = vec![];
+
+  tls
+    .write_all(request)
+    .map_err(|error| format!("Could not send the request: {error}"))?;
+
+  match tls.read_to_end(&mut data) {
+    Ok(_) => {}
+    // Many servers close the connection without a TLS close_notify
+    Err(error)
+      if error.kind() == std::io::ErrorKind::UnexpectedEof
+        && !data.is_empty() => {}
+    Err(error) => return Err(format!("Could not read the response: {error}")),
+  }
+
+  Response::parse(&data)
+}
@@ -0,0 +1,216 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A Gopher client
+//!
+//! Menus are converted to Gemtext so that they can be navigated like any
+//! other page, and search items ask for their query through the same prompt
+//! as Gemini's input responses.
+
+use std::io::{Read, Write};
+
+use url::Url;
+
+use crate::request::Response;
+
+const DEFAULT_PORT: u16 = 70;
+
+/// Request `url`, a `gopher://host[:port]/<type><selector>[?<query>]` URL
+pub fn request(url: &Url) -> Result<Response, String> {
+  let host = url.host_str().ok_or_else(|| format!("{url} has no host"))?;
+  let path = crate::url::percent_decode(url.path());
+  let path = path.strip_prefix(b"/").unwrap_or(&path);
+  let (item_type, selector) = path
+    .split_first()
+    .map_or((b'1', &[][..]), |(item_type, selector)| {
+      (*item_type, selector)
+    });
+  let query = url.query().map(crate::url::percent_decode);
+
+  if item_type == b'7' && query.is_none() {
+    return Ok(Response {
+      status: 10,
+      meta:   "Search".to_string(),
+      body:   vec![],
+    });
+  }
+
+  let mut stream =
+    crate::request::connect(host, url.port().unwrap_or(DEFAULT_PORT))?;
+  let mut line = selector.to_vec();
+
+  if let Some(query) = query {
+    line.push(b'\t');
+    line.extend(query);
+  }
+
+  line.extend(b"\r\n");
+
+  let mut body = vec![];
+
+  stream
+    .write_all(&line)
+    .and_then(|()| stream.read_to_end(&mut body))
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  Ok(match item_type {
+    b'1' | b'7' => Response::success("text/gemini", menu(&text(&body))),
+    b'0' => Response::success("text/plain", text(&body)),
+    b'h' => Response::success("text/html", body),
+    b'g' => Response::success("image/gif", body),
+    _ => Response::success("application/octet-stream", body),
+  })
+}
+
+/// The body of a text response, without the terminating `.` line and with
+/// the `.` servers add to lines which start with one removed
+fn text(body: &[u8]) -> String {
+  let text = String::from_utf8_lossy(body);
+  let text = text.trim_end_matches(['\r', '\n']);
+
+  text
+    .strip_suffix("\n.")
+    .or_else(|| (text == ".").then_some(""))
+    .unwrap_or(text)
+    .split('\n')
+    .map(|line| {
+      let line = line.strip_suffix('\r').unwrap_or(line);
+
+      line
+        .strip_prefix('.')
+        .filter(|rest| rest.starts_with('.'))
+        .unwrap_or(line)
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Convert a Gopher menu to Gemtext, with a link for every item but
+/// informational lines and errors
+fn menu(menu: &str) -> String {
+  let mut gemtext = String::new();
+
+  for line in menu.lines() {
+    let mut fields = line.split('\t');
+    let (display, selector, host, port) = (
+      fields.next().unwrap_or_default(),
+      fields.next().unwrap_or_default(),
+      fields.next().unwrap_or_default(),
+      fields.next().unwrap_or_default().trim(),
+    );
+    let mut characters = display.chars();
+    let item_type = characters.next().unwrap_or('i');
+    let display = characters.as_str();
+    let target = match item_type {
+      'i' | '3' => None,
+      'h' if selector.starts_with("URL:") =>
+        Some(selector.trim_start_matches("URL:").to_string()),
+      '8' => Some(format!("telnet://{host}:{port}")),
+      _ => link(item_type, selector, host, port),
+    };
+
+    if let Some(target) = target {
+      gemtext.push_str("=> ");
+      gemtext.push_str(&target);
+      gemtext.push(' ');
+      gemtext.push_str(display);
+      gemtext.push('\n');
+    } else {
+      crate::document::push_text(&mut gemtext, display);
+    }
+  }
+
+  gemtext
+}
+
+fn link(
+  item_type: char,
+  selector: &str,
+  host: &str,
+  port: &str,
+) -> Option<String> {
+  let authority = if port.is_empty() || port == "70" {
+    host.to_string()
+  } else {
+    format!("{host}:{port}")
+  };
+  let mut url = Url::parse(&format!("gopher://{authority}/")).ok()?;
+
+  url.set_path(&format!("/{item_type}{selector}"));
+
+  Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_text() {
+    for (body, expected) in [
+      ("", ""),
+      (".\r\n", ""),
+      ("one\r\ntwo\r\n.\r\n", "one\ntwo"),
+      ("one\ntwo\n", "one\ntwo"),
+      ("one\r\n\r\nthree\r\n.\r\n", "one\n\nthree"),
+      ("..dotted\r\n...\r\n.\r\n", ".dotted\n.."),
+      ("a .. b\r\n.\r\n", "a .. b"),
+      ("not the end\r\n.\r\nmore\r\n", "not the end\n.\nmore"),
+    ] {
+      assert_eq!(text(body.as_bytes()), expected, "{body:?}");
+    }
+  }
+
+  #[test]
+  fn converts_menus() {
+    for (line, expected) in [
+      ("iWelcome\tfake\t(NULL)\t0", "Welcome"),
+      ("i\t\t\t", ""),
+      ("i# Not a heading\t\t\t", " # Not a heading"),
+      ("i=> Not a link\t\t\t", " => Not a link"),
+      ("i```\t\t\t", " ```"),
+      ("i=: Not a prompt\t\t\t", " =: Not a prompt"),
+      ("3Not found\t\terror.host\t1", "Not found"),
+      (
+        "1Phlog\t/phlog\tfuwn.me\t70",
+        "=> gopher://fuwn.me/1/phlog Phlog",
+      ),
+      (
+        "0About\t/about.txt\tfuwn.me\t7070",
+        "=> gopher://fuwn.me:7070/0/about.txt About",
+      ),
+      (
+        "7Search\t/search\tfuwn.me\t70",
+        "=> gopher://fuwn.me/7/search Search",
+      ),
+      (
+        "1Spaced out\t/a b\tfuwn.me\t70",
+        "=> gopher://fuwn.me/1/a%20b Spaced out",
+      ),
+      (
+        "hWeb\tURL:https://fuwn.me/\tfuwn.me\t70",
+        "=> https://fuwn.me/ Web",
+      ),
+      (
+        "8Login\t\tbbs.fuwn.me\t23",
+        "=> telnet://bbs.fuwn.me:23 Login",
+      ),
+    ] {
+      assert_eq!(menu(line), format!("{expected}\n"), "{line:?}");
+    }
+  }
+}
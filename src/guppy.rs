@@ -0,0 +1,277 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A Guppy client
+//!
+//! Guppy sends Gemtext over UDP. The request is a single packet holding the
+//! URL, which is sent again until the server answers. A successful response
+//! is split across packets numbered from a random sequence number, the first
+//! carrying the MIME type and the last carrying no data. Each packet is
+//! acknowledged by sending its sequence number back, and the server sends
+//! every packet which was not acknowledged again, so packets may arrive more
+//! than once and out of order. Other responses are a single packet whose
+//! "sequence number" is a status: `1` for input, `3` for a redirect and `4`
+//! for an error.
+
+use std::{
+  collections::BTreeMap,
+  net::{ToSocketAddrs, UdpSocket},
+  time::{Duration, Instant},
+};
+
+use url::Url;
+
+use crate::request::Response;
+
+const DEFAULT_PORT: u16 = 6775;
+/// How long to wait for a packet before sending the request again
+const RETRANSMIT: Duration = Duration::from_secs(2);
+/// The largest packet accepted
+const MAX_PACKET: usize = 65_535;
+
+/// A response packet, its sequence number or status, header and data
+struct Packet<'a> {
+  number: u32,
+  meta:   Option<&'a str>,
+  data:   &'a [u8],
+}
+impl<'a> Packet<'a> {
+  fn parse(packet: &'a [u8]) -> Option<Self> {
+    let end = packet.windows(2).position(|window| window == b"\r\n")?;
+    let header = std::str::from_utf8(&packet[..end]).ok()?;
+    let (number, meta) = header
+      .split_once(' ')
+      .map_or((header, None), |(number, meta)| (number, Some(meta)));
+
+    Some(Self {
+      number: number.parse().ok()?,
+      meta,
+      data: &packet[end + 2..],
+    })
+  }
+}
+
+/// Request `url`, a `guppy://host[:port]/path[?input]` URL
+pub fn request(url: &Url) -> Result<Response, String> {
+  let host = url.host_str().ok_or_else(|| format!("{url} has no host"))?;
+  let address = (host, url.port().unwrap_or(DEFAULT_PORT))
+    .to_socket_addrs()
+    .map_err(|error| format!("Could not resolve {host}: {error}"))?
+    .next()
+    .ok_or_else(|| format!("Could not resolve {host}"))?;
+  let socket = UdpSocket::bind(if address.is_ipv4() {
+    "0.0.0.0:0"
+  } else {
+    "[::]:0"
+  })
+  .and_then(|socket| socket.connect(address).map(|()| socket))
+  .map_err(|error| format!("Could not connect to {host}: {error}"))?;
+
+  exchange(&socket, url, RETRANSMIT, crate::request::TIMEOUT)
+}
+
+/// Request `url` over a connected `socket`, sending the request again every
+/// `retransmit` until the server answers and giving up after `timeout`
+fn exchange(
+  socket: &UdpSocket,
+  url: &Url,
+  retransmit: Duration,
+  timeout: Duration,
+) -> Result<Response, String> {
+  socket
+    .set_read_timeout(Some(retransmit))
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  let request = format!("{url}\r\n");
+  let deadline = Instant::now() + timeout;
+  let mut buffer = vec![0; MAX_PACKET];
+  // The first sequence number and MIME type, once the first packet arrived
+  let mut first: Option<(u32, String)> = None;
+  let mut last: Option<u32> = None;
+  let mut chunks = BTreeMap::new();
+
+  socket
+    .send(request.as_bytes())
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  loop {
+    if Instant::now() > deadline {
+      return Err(format!("Timed out waiting for {url}"));
+    }
+
+    let length = match socket.recv(&mut buffer) {
+      Ok(length) => length,
+      Err(error)
+        if matches!(
+          error.kind(),
+          std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        ) =>
+      {
+        // Until the server answers, the request may have been lost
+        if chunks.is_empty() {
+          socket
+            .send(request.as_bytes())
+            .map_err(|error| format!("Could not request {url}: {error}"))?;
+        }
+
+        continue;
+      }
+      Err(error) => return Err(format!("Could not request {url}: {error}")),
+    };
+    let Some(packet) = Packet::parse(&buffer[..length]) else {
+      continue;
+    };
+
+    if chunks.is_empty() && first.is_none() {
+      let status = match packet.number {
+        1 => Some(10),
+        3 => Some(30),
+        4 => Some(40),
+        _ => None,
+      };
+
+      if let Some(status) = status {
+        return Ok(Response {
+          status,
+          meta: packet.meta.unwrap_or_default().trim().to_string(),
+          body: vec![],
+        });
+      }
+    }
+
+    socket
+      .send(format!("{}\r\n", packet.number).as_bytes())
+      .map_err(|error| format!("Could not acknowledge {url}: {error}"))?;
+
+    if let Some(meta) = packet.meta {
+      first = Some((packet.number, meta.trim().to_string()));
+    } else if packet.data.is_empty() {
+      last = Some(packet.number);
+    }
+
+    chunks.insert(packet.number, packet.data.to_vec());
+
+    if let (Some((first, mime)), Some(last)) = (&first, last) {
+      // Sequence numbers are unique keys, so the response is complete once
+      // as many packets as the range spans were received
+      if *first <= last
+        && usize::try_from(last - *first)
+          .is_ok_and(|span| chunks.range(*first..=last).count() == span + 1)
+      {
+        return Ok(Response::success(
+          mime,
+          chunks
+            .range(*first..=last)
+            .flat_map(|(_, chunk)| chunk.iter().copied())
+            .collect::<Vec<_>>(),
+        ));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Request a page from a server on the loopback interface which answers
+  /// the request with the given number with the packets of `answer`
+  fn fetch(
+    answer: impl Fn(usize) -> Vec<&'static [u8]> + Send + 'static,
+  ) -> Result<Response, String> {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    client.connect(server.local_addr().unwrap()).unwrap();
+    server
+      .set_read_timeout(Some(Duration::from_secs(2)))
+      .unwrap();
+    std::thread::spawn(move || {
+      let mut buffer = vec![0; MAX_PACKET];
+      let mut requests = 0;
+
+      while let Ok((length, peer)) = server.recv_from(&mut buffer) {
+        // Acknowledgements are not answered
+        if !buffer[..length].starts_with(b"guppy://") {
+          continue;
+        }
+
+        for packet in answer(requests) {
+          server.send_to(packet, peer).unwrap();
+        }
+
+        requests += 1;
+      }
+    });
+
+    exchange(
+      &client,
+      &Url::parse("guppy://localhost/").unwrap(),
+      Duration::from_millis(50),
+      Duration::from_secs(1),
+    )
+  }
+
+  #[test]
+  fn reassembles_reordered_and_repeated_packets() {
+    let response = fetch(|_| {
+      vec![
+        b"7\r\nworld\n",
+        b"6 text/gemini\r\nhello ",
+        b"7\r\nworld\n",
+        b"8\r\n",
+        b"6 text/gemini\r\nhello ",
+      ]
+    })
+    .unwrap();
+
+    assert_eq!(response.status, 20);
+    assert_eq!(response.meta, "text/gemini");
+    assert_eq!(response.body, b"hello world\n");
+  }
+
+  #[test]
+  fn sends_lost_requests_again() {
+    let response = fetch(|request| {
+      if request == 0 {
+        vec![]
+      } else {
+        vec![b"3 guppy://localhost/moved\r\n"]
+      }
+    })
+    .unwrap();
+
+    assert_eq!(response.status, 30);
+    assert_eq!(response.meta, "guppy://localhost/moved");
+  }
+
+  #[test]
+  fn times_out_without_an_answer() {
+    assert!(matches!(
+      fetch(|_| vec![]),
+      Err(error) if error.starts_with("Timed out")
+    ));
+  }
+
+  #[test]
+  fn times_out_waiting_for_missing_packets() {
+    let response =
+      fetch(|_| vec![b"6 text/gemini\r\nhello", b"4294967295\r\n"]);
+
+    assert!(matches!(response, Err(error) if error.starts_with("Timed out")));
+  }
+}
@@ -0,0 +1,91 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Client certificates, which capsules use to tell visitors apart
+//!
+//! An identity named `work` is read from `identities/work.pem` in Sydney's
+//! configuration directory, a PEM file holding the certificate followed by
+//! its private key. Identities are only presented to the hosts they are
+//! mapped to in the `[identities]` section of the configuration, and to
+//! Misfin servers by the `misfin_identity` configuration key.
+
+use std::path::PathBuf;
+
+use rustls::{Certificate, PrivateKey};
+use url::Url;
+
+use crate::config::Config;
+
+pub struct Identity {
+  pub certificates: Vec<Certificate>,
+  pub key:          PrivateKey,
+}
+
+pub fn directory() -> Option<PathBuf> {
+  crate::config::directory().map(|directory| directory.join("identities"))
+}
+
+/// The identity the configuration asks to present to the host of `url`, if
+/// any
+pub fn configured(
+  config: &Config,
+  url: &Url,
+) -> Result<Option<Identity>, String> {
+  url
+    .host_str()
+    .and_then(|host| config.identity(host))
+    .map(load)
+    .transpose()
+}
+
+pub fn load(name: &str) -> Result<Identity, String> {
+  if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+    return Err(format!("\"{name}\" is not a valid identity name"));
+  }
+
+  let path = directory()
+    .ok_or_else(|| "Could not locate the configuration directory".to_string())?
+    .join(format!("{name}.pem"));
+  let content = std::fs::read(&path).map_err(|error| {
+    format!("Could not read identity {}: {error}", path.display())
+  })?;
+  let mut certificates = vec![];
+  let mut key = None;
+
+  for item in rustls_pemfile::read_all(&mut content.as_slice())
+    .map_err(|error| format!("Could not parse {}: {error}", path.display()))?
+  {
+    match item {
+      rustls_pemfile::Item::X509Certificate(certificate) =>
+        certificates.push(Certificate(certificate)),
+      rustls_pemfile::Item::PKCS8Key(der)
+      | rustls_pemfile::Item::RSAKey(der)
+      | rustls_pemfile::Item::ECKey(der) => key = key.or(Some(PrivateKey(der))),
+      _ => {}
+    }
+  }
+
+  if certificates.is_empty() {
+    return Err(format!("{} holds no certificate", path.display()));
+  }
+
+  Ok(Identity {
+    certificates,
+    key: key
+      .ok_or_else(|| format!("{} holds no private key", path.display()))?,
+  })
+}
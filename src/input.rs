@@ -15,15 +15,112 @@
 // Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crossterm::event::KeyCode;
-use url::Url;
+//! Key and mouse handling, and the key bindings listed on `about:keys`
 
-use crate::command::Command;
+use crossterm::event::{
+  KeyCode,
+  KeyModifiers,
+  MouseButton,
+  MouseEvent,
+  MouseEventKind,
+};
 
+/// A key binding, as listed on `about:keys`
+pub struct Binding {
+  /// The mode the keys are bound in
+  pub mode:        &'static str,
+  /// The keys, as they are typed
+  pub keys:        &'static str,
+  /// What the keys do
+  pub description: &'static str,
+}
+
+const fn binding(
+  mode: &'static str,
+  keys: &'static str,
+  description: &'static str,
+) -> Binding {
+  Binding {
+    mode,
+    keys,
+    description,
+  }
+}
+
+/// Every key binding handled below, used to generate `about:keys`
+pub static BINDINGS: &[Binding] = &[
+  binding("Normal", ":", "Enter a command"),
+  binding("Normal", "j, Down", "Select the next line"),
+  binding("Normal", "k, Up", "Select the previous line"),
+  binding("Normal", "gg", "Select the first line"),
+  binding("Normal", "G", "Select the last line"),
+  binding("Normal", "]]", "Jump to the next heading"),
+  binding("Normal", "[[", "Jump to the previous heading"),
+  binding("Normal", "T", "Toggle the table of contents"),
+  binding("Normal", "Enter", "Follow the selected link"),
+  binding("Normal", "h, Left", "Go back"),
+  binding("Normal", "l, Right", "Go forward"),
+  binding("Normal", "r", "Reload the page"),
+  binding("Normal", "gt", "Switch to the next tab"),
+  binding("Normal", "gT", "Switch to the previous tab"),
+  binding("Normal", "yy", "Yank the URL of the page"),
+  binding("Normal", "yl", "Yank the target of the selected link"),
+  binding("Normal", "V", "Start selecting lines"),
+  binding("Normal", "Esc", "Clear the selection"),
+  binding("Normal", "Backspace", "Dismiss the error or notification"),
+  binding(
+    "Visual line",
+    "j, k, Down, Up, gg, G",
+    "Extend the selection",
+  ),
+  binding("Visual line", "y", "Yank the selected lines as Gemtext"),
+  binding("Visual line", "Esc, V", "Stop selecting lines"),
+  binding("Command line", "Enter", "Run the command"),
+  binding("Command line", "Esc", "Cancel the command"),
+  binding("Command line", "Tab, Shift-Tab", "Cycle through completions"),
+  binding(
+    "Command line",
+    "Up, Down, Ctrl-p, Ctrl-n",
+    "Search the history for commands starting with the typed text",
+  ),
+  binding("Command line", "Left, Right, Ctrl-b, Ctrl-f", "Move the cursor"),
+  binding(
+    "Command line",
+    "Ctrl-Left, Ctrl-Right, Alt-b, Alt-f",
+    "Move the cursor by a word",
+  ),
+  binding("Command line", "Home, End, Ctrl-a, Ctrl-e", "Move to either end"),
+  binding("Command line", "Ctrl-w", "Delete the word before the cursor"),
+  binding("Command line", "Ctrl-u", "Delete everything before the cursor"),
+  binding("Command line", "Ctrl-k", "Delete everything after the cursor"),
+  binding(
+    "Command line",
+    "Backspace, Ctrl-h",
+    "Delete the character before the cursor",
+  ),
+  binding(
+    "Command line",
+    "Delete, Ctrl-d",
+    "Delete the character under the cursor",
+  ),
+  binding("Input prompt", "Enter", "Send the response"),
+  binding("Input prompt", "Esc", "Cancel the response"),
+  binding("Input prompt", "Backspace", "Delete the last character"),
+  binding("Mouse", "Wheel", "Scroll the page"),
+  binding("Mouse", "Left click", "Select a line and follow it if it is a link"),
+  binding("Mouse", "Middle click", "Open a link in a new tab"),
+];
+
+/// Whether keys move around the page, edit the command line or extend a
+/// visual line selection
 #[derive(PartialEq, Eq)]
 pub enum Mode {
+  /// Keys move around the page
   Normal,
+  /// Keys edit the command line
   Editing,
+  /// Keys extend a visual line selection
+  Visual,
 }
 
 fn handle_input_response(
@@ -32,39 +129,27 @@ fn handle_input_response(
 ) -> bool {
   match key.code {
     KeyCode::Enter => {
-      let new_url = match app.url.to_string().split('?').next() {
-        Some(base_url) => {
-          format!("{}?{}", base_url, app.response_input)
-        }
-        None => "".to_string(),
-      };
-
-      if new_url.is_empty() {
-        app.error = Some("Invalid base URL".to_string());
+      let mut url = app.upload.take().unwrap_or_else(|| app.url.clone());
 
-        return false;
-      }
+      url.set_query(Some(&crate::url::percent_encode(&app.response_input)));
+      url.set_fragment(None);
 
-      match Url::parse(&new_url) {
-        Ok(url) => {
-          app.set_url(url);
-          app.make_request();
-          app.response_input.clear();
-          app.response_input_text.clear();
+      app.set_url(url);
+      app.make_request();
+      app.response_input.clear();
+      app.response_input_text.clear();
 
-          app.accept_response_input = false;
-        }
-        Err(error) => {
-          app.error = Some(error.to_string());
-        }
-      }
+      app.accept_response_input = false;
     }
     KeyCode::Esc => {
       app.accept_response_input = false;
 
       app.response_input.clear();
       app.response_input_text.clear();
-      app.go_back();
+
+      if app.upload.take().is_none() {
+        app.go_back();
+      }
     }
     KeyCode::Char(c) => {
       app.response_input.push(c);
@@ -78,10 +163,13 @@ fn handle_input_response(
   false
 }
 
+#[allow(clippy::too_many_lines)]
 fn handle_normal_input(
   app: &mut crate::App,
   key: crossterm::event::KeyEvent,
 ) -> bool {
+  let pending = std::mem::take(&mut app.command_stroke_history);
+
   match key.code {
     KeyCode::Char(':') => {
       app.input.clear();
@@ -90,7 +178,7 @@ fn handle_normal_input(
       app.error = None;
     }
     KeyCode::Char('r') => {
-      app.make_request();
+      app.reload();
     }
     KeyCode::Esc => app.items.unselect(),
     KeyCode::Down | KeyCode::Char('j') => {
@@ -106,6 +194,20 @@ fn handle_normal_input(
     KeyCode::Char('h') | KeyCode::Left => {
       app.go_back();
     }
+    KeyCode::Char('l') if pending.contains(&KeyCode::Char('y')) => {
+      if let Some(link) = app
+        .items
+        .state
+        .selected()
+        .and_then(|selected| app.items.items[selected].link.clone())
+      {
+        let target = app.base().join(&link).map_or(link, |url| url.to_string());
+
+        app.yank(&target);
+      } else {
+        app.error = Some("The selected line is not a link".to_string());
+      }
+    }
     KeyCode::Char('l') | KeyCode::Right => {
       if let Some(url) = app.previous_capsule.clone() {
         app.set_url(url);
@@ -116,46 +218,56 @@ fn handle_normal_input(
       }
     }
     KeyCode::Char('G') => app.items.last(),
-    KeyCode::Char('g') => {
-      if app.command_stroke_history.contains(&key.code) {
+    KeyCode::Char('g') =>
+      if pending.contains(&key.code) {
         app.items.first();
-        app.command_stroke_history.clear();
-      } else if app.command_stroke_history.is_empty() {
+      } else {
+        app.command_stroke_history.push(key.code);
+      },
+    KeyCode::Char('t') if pending.contains(&KeyCode::Char('g')) =>
+      app.next_tab(),
+    KeyCode::Char('T') if pending.contains(&KeyCode::Char('g')) =>
+      app.previous_tab(),
+    KeyCode::Char(']') =>
+      if pending.contains(&key.code) {
+        app.next_heading();
+      } else {
+        app.command_stroke_history.push(key.code);
+      },
+    KeyCode::Char('[') =>
+      if pending.contains(&key.code) {
+        app.previous_heading();
+      } else {
+        app.command_stroke_history.push(key.code);
+      },
+    KeyCode::Char('y') =>
+      if pending.contains(&key.code) {
+        let url = app.url.to_string();
+
+        app.yank(&url);
+      } else {
         app.command_stroke_history.push(key.code);
+      },
+    KeyCode::Char('V') => {
+      if app.items.state.selected().is_none() {
+        app.items.first();
       }
+
+      app.visual_anchor = app.items.selected;
+      app.input_mode = Mode::Visual;
+      app.error = None;
     }
-    KeyCode::Backspace => app.error = None,
+    KeyCode::Char('T') => app.show_toc = !app.show_toc,
+    KeyCode::Backspace =>
+      if app.error.is_some() {
+        app.error = None;
+      } else {
+        app.notification = None;
+      },
     KeyCode::Enter => {
       app.error = None;
 
-      if let Some(link) = &app.items.items[app.items.selected].1 {
-        if !link.starts_with("gemini://") && link.contains("://") {
-        } else {
-          let the_url = &if link.starts_with('/') {
-            if let Some(host) = app.url.host_str() {
-              format!("gemini://{}{}", host, link)
-            } else {
-              app.error = Some("URL has no host".to_string());
-
-              return false;
-            }
-          } else if link.starts_with("gemini://") {
-            link.to_string()
-          } else if !link.starts_with('/') && !link.starts_with("gemini://") {
-            format!("{}/{}", app.url.to_string().trim_end_matches('/'), link)
-          } else {
-            app.url.to_string()
-          };
-
-          match Url::parse(the_url) {
-            Ok(the_actual_url) => {
-              app.set_url(the_actual_url);
-              app.make_request();
-            }
-            Err(error) => app.error = Some(error.to_string()),
-          }
-        }
-      }
+      app.follow_item(app.items.selected);
     }
     _ => {}
   }
@@ -170,82 +282,60 @@ fn handle_editing_input(
   match key.code {
     KeyCode::Enter => {
       app.command_history.reverse();
-      app.command_history.push(app.input.to_string());
+      app.command_history.push(app.input.text().to_string());
       app.command_history.reverse();
 
-      match Command::from(app.input.to_string()) {
-        Command::Quit => return true,
-        Command::Open(to) => {
-          if let Some(to) = to {
-            match Url::parse(&crate::url::prefix_gemini(&to)) {
-              Ok(url) => {
-                app.set_url(url);
-                app.make_request();
-              }
-              Err(error) => app.error = Some(error.to_string()),
-            }
-          } else {
-            app.error = Some("No URL provided for open command".to_string());
-          }
-        }
-        Command::Unknown => {
-          app.error = Some(format!("\"{}\" is not a valid command", app.input));
-        }
-        Command::Wrap(at, error) => {
-          if let Some(error) = error {
-            app.error = Some(error);
-          } else {
-            app.error = None;
-            app.wrap_at = at;
-
-            app.make_request();
-          }
-        }
-        Command::Help => {
-          app.set_url(
-            Url::parse("gemini://fuwn.me/blog/technology/gemini?referrer=sydney").unwrap(),
-          );
-          app.make_request();
-        }
-      }
-
       app.input_mode = Mode::Normal;
-      app.command_history_cursor = 0;
-    }
-    KeyCode::Char(c) => {
-      app.input.push(c);
-    }
-    KeyCode::Up => {
-      if let Some(command) = app.command_history.get(app.command_history_cursor)
-      {
-        app.input = command.to_string();
 
-        if app.command_history_cursor + 1 < app.command_history.len() {
-          app.command_history_cursor += 1;
-        }
+      let line = app.input.text().to_string();
+
+      if crate::command::execute(app, &line) {
+        return true;
       }
     }
-    KeyCode::Down => {
-      let mut dead_set = false;
+    KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) =>
+      match c {
+        'a' => app.input.move_home(),
+        'e' => app.input.move_end(),
+        'b' => app.input.move_left(),
+        'f' => app.input.move_right(),
+        'w' => app.input.delete_word(),
+        'u' => app.input.delete_to_start(),
+        'k' => app.input.delete_to_end(),
+        'd' => app.input.delete(),
+        'h' => app.input.backspace(),
+        'p' => app.input.history_previous(&app.command_history),
+        'n' => app.input.history_next(&app.command_history),
+        _ => {}
+      },
+    KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) =>
+      match c {
+        'b' => app.input.move_word_left(),
+        'f' => app.input.move_word_right(),
+        _ => {}
+      },
+    KeyCode::Char(c) => app.input.insert(c),
+    KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) =>
+      app.input.move_word_left(),
+    KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) =>
+      app.input.move_word_right(),
+    KeyCode::Left => app.input.move_left(),
+    KeyCode::Right => app.input.move_right(),
+    KeyCode::Home => app.input.move_home(),
+    KeyCode::End => app.input.move_end(),
+    KeyCode::Delete => app.input.delete(),
+    KeyCode::Tab | KeyCode::BackTab => {
+      let mut input = std::mem::take(&mut app.input);
 
-      if app.command_history_cursor > 0 {
-        app.command_history_cursor -= 1;
-      } else {
-        dead_set = true;
-      }
+      input.complete(key.code == KeyCode::Tab, |line, start| {
+        crate::completion::candidates(app, line, start)
+      });
 
-      if let Some(command) = app.command_history.get(app.command_history_cursor)
-      {
-        app.input = command.to_string();
-      }
-
-      if dead_set {
-        app.input.clear();
-      }
-    }
-    KeyCode::Backspace => {
-      app.input.pop();
+      app.input = input;
     }
+    KeyCode::Up => app.input.history_previous(&app.command_history),
+    KeyCode::Down => app.input.history_next(&app.command_history),
+    KeyCode::Backspace => app.input.backspace(),
     KeyCode::Esc => {
       app.input_mode = Mode::Normal;
 
@@ -257,10 +347,94 @@ fn handle_editing_input(
   false
 }
 
+fn handle_visual_input(
+  app: &mut crate::App,
+  key: crossterm::event::KeyEvent,
+) -> bool {
+  let pending = std::mem::take(&mut app.command_stroke_history);
+
+  match key.code {
+    KeyCode::Down | KeyCode::Char('j') => app.items.next(),
+    KeyCode::Up | KeyCode::Char('k') => app.items.previous(),
+    KeyCode::Char('G') => app.items.last(),
+    KeyCode::Char('g') =>
+      if pending.contains(&key.code) {
+        app.items.first();
+      } else {
+        app.command_stroke_history.push(key.code);
+      },
+    KeyCode::Char('y') => {
+      let (start, end) = app.visual_range();
+      let text = app
+        .source
+        .lines()
+        .skip(start)
+        .take(end - start + 1)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+      app.yank(&text);
+
+      app.input_mode = Mode::Normal;
+    }
+    KeyCode::Esc | KeyCode::Char('V') => app.input_mode = Mode::Normal,
+    _ => {}
+  }
+
+  false
+}
+
+/// Scroll with the wheel, follow links with a left click and open them in a
+/// new tab with a middle click
+pub fn handle_mouse(app: &mut crate::App, mouse: MouseEvent) {
+  if app.input_mode != Mode::Normal || app.accept_response_input {
+    return;
+  }
+
+  match mouse.kind {
+    MouseEventKind::ScrollDown => app.items.scroll_down(3),
+    MouseEventKind::ScrollUp => app.items.scroll_up(3),
+    MouseEventKind::Down(button) => {
+      let Some(index) = app.item_at(mouse.column, mouse.row) else {
+        return;
+      };
+      let link = app.items.items[index].link.clone();
+
+      match button {
+        MouseButton::Left => {
+          app.items.select(index);
+
+          app.error = None;
+
+          app.follow_item(index);
+        }
+        MouseButton::Middle =>
+          if let Some(link) = link {
+            match crate::url::resolve(app.base(), &link) {
+              Ok(Some(url)) => {
+                app.message = Some(format!("Opened {url} in a new tab"));
+
+                app.open_tab(url);
+              }
+              Ok(None) => {}
+              Err(error) => app.error = Some(error),
+            }
+          },
+        MouseButton::Right => {}
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Handle a key press in the current mode, returning whether Sydney should
+/// quit
 pub fn handle_key_strokes(
   app: &mut crate::App,
   key: crossterm::event::KeyEvent,
 ) -> bool {
+  app.message = None;
+
   match app.input_mode {
     Mode::Normal => {
       if app.accept_response_input {
@@ -270,5 +444,196 @@ pub fn handle_key_strokes(
       }
     }
     Mode::Editing => handle_editing_input(app, key),
+    Mode::Visual => handle_visual_input(app, key),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyEvent;
+  use url::Url;
+
+  use super::*;
+  use crate::{config::Config, stateful_list::StatefulList, App};
+
+  const MODES: [&str; 4] =
+    ["Normal", "Visual line", "Command line", "Input prompt"];
+
+  /// The keys listed in `keys`, only the first of a sequence such as `gg`
+  fn parse(keys: &str) -> Vec<KeyEvent> {
+    keys
+      .split(", ")
+      .map(|key| {
+        let (modifiers, key) = [
+          ("Ctrl-", KeyModifiers::CONTROL),
+          ("Alt-", KeyModifiers::ALT),
+        ]
+        .into_iter()
+        .find_map(|(prefix, modifiers)| {
+          Some((modifiers, key.strip_prefix(prefix)?))
+        })
+        .unwrap_or((KeyModifiers::NONE, key));
+        let code = match key {
+          "Enter" => KeyCode::Enter,
+          "Esc" => KeyCode::Esc,
+          "Backspace" => KeyCode::Backspace,
+          "Delete" => KeyCode::Delete,
+          "Tab" => KeyCode::Tab,
+          "Shift-Tab" => KeyCode::BackTab,
+          "Up" => KeyCode::Up,
+          "Down" => KeyCode::Down,
+          "Left" => KeyCode::Left,
+          "Right" => KeyCode::Right,
+          "Home" => KeyCode::Home,
+          "End" => KeyCode::End,
+          _ => KeyCode::Char(key.chars().next().unwrap()),
+        };
+
+        KeyEvent::new(code, modifiers)
+      })
+      .collect()
+  }
+
+  /// Every key worth pressing, with modifiers only where they are told apart
+  fn keys(modified: bool) -> Vec<KeyEvent> {
+    let mut keys = [
+      KeyCode::Enter,
+      KeyCode::Esc,
+      KeyCode::Backspace,
+      KeyCode::Delete,
+      KeyCode::Tab,
+      KeyCode::BackTab,
+      KeyCode::Up,
+      KeyCode::Down,
+      KeyCode::Left,
+      KeyCode::Right,
+      KeyCode::Home,
+      KeyCode::End,
+      KeyCode::PageUp,
+      KeyCode::PageDown,
+    ]
+    .into_iter()
+    .chain((' '..='~').map(KeyCode::Char))
+    .map(|code| KeyEvent::new(code, KeyModifiers::NONE))
+    .collect::<Vec<_>>();
+
+    if modified {
+      for modifiers in [KeyModifiers::CONTROL, KeyModifiers::ALT] {
+        keys.extend(
+          ('a'..='z').map(|c| KeyEvent::new(KeyCode::Char(c), modifiers)),
+        );
+      }
+
+      keys.extend(
+        [KeyCode::Left, KeyCode::Right]
+          .map(|code| KeyEvent::new(code, KeyModifiers::CONTROL)),
+      );
+    }
+
+    keys
+  }
+
+  /// An app in `mode`, in a state where every key has something to act on
+  fn app(mode: &str) -> App {
+    let mut app = App::new(Config::default());
+    let page = "# Page\n=> gemini://capsule/next Next\nText\n## Heading\n";
+
+    app.source = page.to_string();
+    app.items = StatefulList::with_items(crate::document::parse(page));
+    app.items.select(1);
+    app.error = Some("Error".to_string());
+    app.notification = Some("Notification".to_string());
+    app.capsule_history = vec![Url::parse("gemini://capsule/").unwrap()];
+    app.previous_capsule = Some(Url::parse("gemini://capsule/next").unwrap());
+    app.command_history = vec!["open gemini://capsule/".to_string()];
+
+    match mode {
+      "Visual line" => {
+        app.input_mode = Mode::Visual;
+        app.visual_anchor = 1;
+      }
+      "Command line" => {
+        app.input_mode = Mode::Editing;
+        app.input.set("open gemini://capsule/next".to_string());
+
+        for _ in 0..5 {
+          app.input.move_left();
+        }
+      }
+      "Input prompt" => {
+        app.accept_response_input = true;
+        app.response_input = "Response".to_string();
+      }
+      _ => {}
+    }
+
+    app
+  }
+
+  fn state(app: &App) -> String {
+    format!(
+      "{} {:?} {} {} {:?} {} {:?} {:?} {:?} {} {:?} {:?} {} {:?} {} {} {:?} \
+       {:?}",
+      app.url,
+      app.input.text(),
+      app.input.cursor(),
+      match app.input_mode {
+        Mode::Normal => "Normal",
+        Mode::Editing => "Editing",
+        Mode::Visual => "Visual",
+      },
+      app.items.state.selected(),
+      app.items.selected,
+      app.error,
+      app.message,
+      app.notification,
+      app.show_toc,
+      app.command_stroke_history,
+      app.response_input,
+      app.accept_response_input,
+      app.capsule_history,
+      app.visual_anchor,
+      app.tabs.len(),
+      app.previous_capsule,
+      app.command_history,
+    )
+  }
+
+  #[test]
+  fn lists_every_handled_key() {
+    for binding in BINDINGS {
+      assert!(
+        MODES.contains(&binding.mode) || binding.mode == "Mouse",
+        "{}",
+        binding.mode
+      );
+    }
+
+    for mode in MODES {
+      let typing = matches!(mode, "Command line" | "Input prompt");
+      let listed = BINDINGS
+        .iter()
+        .filter(|binding| binding.mode == mode)
+        .flat_map(|binding| parse(binding.keys))
+        .collect::<Vec<_>>();
+
+      for key in keys(mode == "Command line") {
+        if listed.contains(&key)
+          || typing
+            && key.modifiers == KeyModifiers::NONE
+            && matches!(key.code, KeyCode::Char(_))
+        {
+          continue;
+        }
+
+        let mut app = app(mode);
+        let before = state(&app);
+
+        assert!(
+          !handle_key_strokes(&mut app, key) && state(&app) == before,
+          "{mode}: {key:?} is handled but not listed"
+        );
+      }
+    }
   }
 }
@@ -0,0 +1,91 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sydney, a Vim-like Gemini client, as a library
+//!
+//! The pieces the `sydney` binary is built from are exposed so that other
+//! front-ends can reuse them:
+//!
+//! - [`document`] parses Gemtext into the items Sydney displays
+//! - [`url`] resolves links against the page they appear on
+//! - [`transport`] fetches URLs through a registry of transports by scheme,
+//!   which answer with a [`request::Response`]
+//! - [`ui`] renders items to a `ratatui` [`Text`](ratatui::text::Text), or
+//!   draws a whole [`App`]
+//! - [`app`] holds the state of the browser, which [`input`] drives from
+//!   terminal events
+//!
+//! ```
+//! use sydney::{config::Config, document, ui};
+//!
+//! let items = document::parse("# Sydney\n=> gemini://fuwn.me Fuwn\n");
+//! let text = ui::render(&items, 80, &Config::default().theme.palette());
+//!
+//! assert_eq!(text.lines.len(), 2);
+//! ```
+
+#![deny(
+  warnings,
+  nonstandard_style,
+  unused,
+  future_incompatible,
+  rust_2018_idioms,
+  unsafe_code,
+  clippy::all,
+  clippy::nursery,
+  clippy::pedantic
+)]
+#![warn(missing_docs)]
+#![recursion_limit = "128"]
+
+mod about;
+pub mod app;
+pub mod bookmarks;
+mod clipboard;
+mod command;
+mod completion;
+pub mod config;
+pub mod document;
+mod download;
+pub mod dump;
+mod editor;
+pub mod export;
+mod finger;
+mod gemini;
+mod gopher;
+mod guppy;
+mod identity;
+pub mod input;
+mod line_editor;
+mod misfin;
+mod nex;
+pub mod request;
+mod session;
+mod spartan;
+mod stateful_list;
+pub mod stdin;
+mod tab;
+pub mod theme;
+mod titan;
+mod toc;
+pub mod transport;
+pub mod ui;
+pub mod update;
+pub mod url;
+mod watch;
+
+pub use app::App;
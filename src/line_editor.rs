@@ -0,0 +1,451 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+/// A single line of editable text with a cursor, used by the command line
+#[derive(Default)]
+pub struct LineEditor {
+  text:       String,
+  /// Byte offset of the cursor within `text`, always on a character
+  /// boundary
+  cursor:     usize,
+  completion: Option<Completion>,
+  history:    Option<HistorySearch>,
+}
+
+struct Completion {
+  start:      usize,
+  candidates: Vec<String>,
+  index:      usize,
+}
+
+struct HistorySearch {
+  prefix: String,
+  index:  usize,
+}
+
+impl LineEditor {
+  pub fn text(&self) -> &str { &self.text }
+
+  pub const fn cursor(&self) -> usize { self.cursor }
+
+  pub const fn is_empty(&self) -> bool { self.text.is_empty() }
+
+  pub fn clear(&mut self) { self.set(String::new()); }
+
+  /// Replace the contents of the line and move the cursor to its end
+  pub fn set(&mut self, text: String) {
+    self.cursor = text.len();
+    self.text = text;
+    self.completion = None;
+    self.history = None;
+  }
+
+  pub fn insert(&mut self, character: char) {
+    self.text.insert(self.cursor, character);
+
+    self.cursor += character.len_utf8();
+
+    self.reset();
+  }
+
+  pub fn backspace(&mut self) {
+    if let Some(previous) = self.previous_boundary() {
+      self.text.replace_range(previous..self.cursor, "");
+
+      self.cursor = previous;
+    }
+
+    self.reset();
+  }
+
+  pub fn delete(&mut self) {
+    if let Some(next) = self.next_boundary() {
+      self.text.replace_range(self.cursor..next, "");
+    }
+
+    self.reset();
+  }
+
+  pub fn move_left(&mut self) {
+    if let Some(previous) = self.previous_boundary() {
+      self.cursor = previous;
+    }
+
+    self.reset();
+  }
+
+  pub fn move_right(&mut self) {
+    if let Some(next) = self.next_boundary() {
+      self.cursor = next;
+    }
+
+    self.reset();
+  }
+
+  pub fn move_home(&mut self) {
+    self.cursor = 0;
+
+    self.reset();
+  }
+
+  pub fn move_end(&mut self) {
+    self.cursor = self.text.len();
+
+    self.reset();
+  }
+
+  pub fn move_word_left(&mut self) {
+    self.cursor = self.word_start();
+
+    self.reset();
+  }
+
+  pub fn move_word_right(&mut self) {
+    let rest = &self.text[self.cursor..];
+    let word = rest.len() - rest.trim_start().len();
+
+    self.cursor += rest[word..]
+      .find(char::is_whitespace)
+      .map_or(rest.len(), |end| word + end);
+
+    self.reset();
+  }
+
+  /// Delete the word before the cursor, like Ctrl-w in a shell
+  pub fn delete_word(&mut self) {
+    let start = self.word_start();
+
+    self.text.replace_range(start..self.cursor, "");
+
+    self.cursor = start;
+
+    self.reset();
+  }
+
+  /// Delete everything before the cursor, like Ctrl-u in a shell
+  pub fn delete_to_start(&mut self) {
+    self.text.replace_range(..self.cursor, "");
+
+    self.cursor = 0;
+
+    self.reset();
+  }
+
+  /// Delete everything after the cursor, like Ctrl-k in a shell
+  pub fn delete_to_end(&mut self) {
+    self.text.truncate(self.cursor);
+
+    self.reset();
+  }
+
+  /// The start of the whitespace separated word which ends at the cursor
+  fn word_start(&self) -> usize {
+    let before = self.text[..self.cursor].trim_end();
+
+    before
+      .rfind(char::is_whitespace)
+      .map_or(0, |space| space + 1)
+  }
+
+  /// Cycle through completions of the word under the cursor
+  ///
+  /// `candidates` is only called when a new completion begins, subsequent
+  /// calls cycle through the candidates it returned in the given direction.
+  pub fn complete(
+    &mut self,
+    forward: bool,
+    candidates: impl FnOnce(&str, usize) -> Vec<String>,
+  ) {
+    let completion = if let Some(completion) = &mut self.completion {
+      let length = completion.candidates.len();
+
+      completion.index = if forward {
+        (completion.index + 1) % length
+      } else {
+        (completion.index + length - 1) % length
+      };
+
+      completion
+    } else {
+      let start = self.text[..self.cursor]
+        .rfind(char::is_whitespace)
+        .map_or(0, |space| space + 1);
+      let candidates = candidates(&self.text[..self.cursor], start);
+
+      if candidates.is_empty() {
+        return;
+      }
+
+      self.completion.insert(Completion {
+        start,
+        index: if forward { 0 } else { candidates.len() - 1 },
+        candidates,
+      })
+    };
+    let candidate = &completion.candidates[completion.index];
+
+    self.text.replace_range(completion.start..self.cursor, candidate);
+
+    self.cursor = completion.start + candidate.len();
+
+    if completion.candidates.len() == 1 {
+      self.completion = None;
+    }
+  }
+
+  /// Recall the next older entry of `history` which starts with the text
+  /// typed before history navigation began
+  pub fn history_previous(&mut self, history: &[String]) {
+    let (prefix, from) = self.history.as_ref().map_or_else(
+      || (self.text.clone(), 0),
+      |search| (search.prefix.clone(), search.index + 1),
+    );
+
+    if let Some((index, entry)) = history
+      .iter()
+      .enumerate()
+      .skip(from)
+      .find(|(_, entry)| entry.starts_with(&prefix))
+    {
+      self.recall(entry.clone(), prefix, index);
+    }
+  }
+
+  /// Recall the next newer entry of `history` matching the search prefix,
+  /// restoring the prefix itself once the newest entry is passed
+  pub fn history_next(&mut self, history: &[String]) {
+    let Some(search) = self.history.take() else {
+      return;
+    };
+
+    if let Some((index, entry)) = history
+      .iter()
+      .enumerate()
+      .take(search.index)
+      .rev()
+      .find(|(_, entry)| entry.starts_with(&search.prefix))
+    {
+      self.recall(entry.clone(), search.prefix, index);
+    } else {
+      self.set(search.prefix);
+    }
+  }
+
+  fn recall(&mut self, entry: String, prefix: String, index: usize) {
+    self.set(entry);
+
+    self.history = Some(HistorySearch {
+      prefix,
+      index,
+    });
+  }
+
+  fn reset(&mut self) {
+    self.completion = None;
+    self.history = None;
+  }
+
+  fn previous_boundary(&self) -> Option<usize> {
+    self.text[..self.cursor]
+      .char_indices()
+      .next_back()
+      .map(|(index, _)| index)
+  }
+
+  fn next_boundary(&self) -> Option<usize> {
+    self.text[self.cursor..]
+      .chars()
+      .next()
+      .map(|character| self.cursor + character.len_utf8())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type Edit = fn(&mut LineEditor);
+
+  /// A line holding `text`, with the cursor at the byte offset of `|`
+  fn editor(text: &str) -> LineEditor {
+    let mut editor = LineEditor::default();
+
+    editor.set(text.replace('|', ""));
+
+    editor.cursor = text.find('|').unwrap();
+
+    editor
+  }
+
+  /// The text of `editor` with `|` marking the cursor
+  fn shown(editor: &LineEditor) -> String {
+    let mut text = editor.text().to_string();
+
+    text.insert(editor.cursor(), '|');
+
+    text
+  }
+
+  #[test]
+  fn moves_and_deletes() {
+    let cases: [(&str, Edit, &str); 14] = [
+      (
+        "open  gemini://|",
+        LineEditor::move_word_left,
+        "open  |gemini://",
+      ),
+      (
+        "open  |gemini://",
+        LineEditor::move_word_left,
+        "|open  gemini://",
+      ),
+      (
+        "|open  gemini://",
+        LineEditor::move_word_left,
+        "|open  gemini://",
+      ),
+      (
+        "|open  gemini://",
+        LineEditor::move_word_right,
+        "open|  gemini://",
+      ),
+      (
+        "open|  gemini://",
+        LineEditor::move_word_right,
+        "open  gemini://|",
+      ),
+      ("open  gemini://|", LineEditor::delete_word, "open  |"),
+      ("open  |", LineEditor::delete_word, "|"),
+      ("open ge|mini", LineEditor::delete_word, "open |mini"),
+      ("open ge|mini", LineEditor::delete_to_start, "|mini"),
+      ("open ge|mini", LineEditor::delete_to_end, "open ge|"),
+      ("日本|語", LineEditor::move_left, "日|本語"),
+      ("日本|語", LineEditor::move_right, "日本語|"),
+      ("日本|語", LineEditor::backspace, "日|語"),
+      ("日本|語", LineEditor::delete, "日本|"),
+    ];
+
+    for (before, action, after) in cases {
+      let mut editor = editor(before);
+
+      action(&mut editor);
+
+      assert_eq!(shown(&editor), after, "{before:?}");
+    }
+  }
+
+  #[test]
+  fn stays_within_the_line() {
+    let mut editor = editor("|é");
+
+    editor.move_left();
+    editor.backspace();
+
+    assert_eq!(shown(&editor), "|é");
+
+    editor.move_end();
+    editor.move_right();
+    editor.delete();
+
+    assert_eq!(shown(&editor), "é|");
+  }
+
+  #[test]
+  fn searches_history_by_prefix() {
+    // Newest first, as the command history is kept
+    let history = ["open c", "wrap 80", "open b", "open a"].map(String::from);
+    let mut editor = editor("op|");
+
+    editor.history_previous(&history);
+
+    assert_eq!(editor.text(), "open c");
+
+    editor.history_previous(&history);
+
+    assert_eq!(editor.text(), "open b");
+
+    editor.history_previous(&history);
+    editor.history_previous(&history);
+
+    assert_eq!(editor.text(), "open a");
+
+    editor.history_next(&history);
+
+    assert_eq!(editor.text(), "open b");
+
+    editor.history_next(&history);
+    editor.history_next(&history);
+
+    assert_eq!(shown(&editor), "op|");
+
+    editor.history_next(&history);
+
+    assert_eq!(shown(&editor), "op|");
+  }
+
+  #[test]
+  fn cycles_through_completions() {
+    let mut editor = editor("open gem|");
+    let mut requests = vec![];
+    let mut complete = |editor: &mut LineEditor, forward| {
+      editor.complete(forward, |before, start| {
+        requests.push((before.to_string(), start));
+
+        vec!["gemini://a".to_string(), "gemini://b".to_string()]
+      });
+    };
+
+    complete(&mut editor, true);
+
+    assert_eq!(shown(&editor), "open gemini://a|");
+
+    complete(&mut editor, true);
+
+    assert_eq!(shown(&editor), "open gemini://b|");
+
+    complete(&mut editor, true);
+
+    assert_eq!(shown(&editor), "open gemini://a|");
+
+    complete(&mut editor, false);
+
+    assert_eq!(shown(&editor), "open gemini://b|");
+
+    editor.insert(' ');
+    complete(&mut editor, false);
+
+    assert_eq!(shown(&editor), "open gemini://b gemini://b|");
+    assert_eq!(
+      requests,
+      [
+        ("open gem".to_string(), 5),
+        ("open gemini://b ".to_string(), 16),
+      ]
+    );
+  }
+
+  #[test]
+  fn completes_a_single_candidate_once() {
+    let mut editor = editor("wr|");
+
+    for _ in 0..2 {
+      editor.complete(true, |before, _| vec![format!("{before}ap")]);
+    }
+
+    assert_eq!(shown(&editor), "wrapap|");
+  }
+}
@@ -26,112 +26,157 @@
   clippy::nursery,
   clippy::pedantic
 )]
-#![recursion_limit = "128"]
 
-mod app;
-mod command;
-mod input;
-mod stateful_list;
-mod ui;
-mod url;
+mod cli;
+
+use std::io::IsTerminal;
 
-use ::url::Url;
-use app::App;
 use crossterm::{event, execute, terminal};
+use sydney::{bookmarks, config, dump, stdin, url, App};
 
+#[allow(clippy::too_many_lines)]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-  let mut args = std::env::args();
-  let mut app = App::new();
-
-  if let Some(arg) = args.nth(1) {
-    match arg.as_str() {
-      "--version" | "-v" => {
-        println!("{}", env!("CARGO_PKG_VERSION"));
+  let cli = cli::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+    eprintln!("sydney: {error}\nTry \"sydney --help\" for more information.");
+
+    std::process::exit(2);
+  });
+  let (mut config, config_error) = match cli
+    .config
+    .as_deref()
+    .map_or_else(config::Config::load, config::Config::load_from)
+  {
+    Ok(config) => (config, None),
+    Err(error) => (config::Config::default(), Some(error)),
+  };
+
+  cli.configure(&mut config);
+
+  let locations = match cli.command {
+    cli::Command::Help => {
+      println!("{}", cli::HELP);
+
+      return Ok(());
+    }
+    cli::Command::Version => {
+      println!("{}", env!("CARGO_PKG_VERSION"));
 
-        return Ok(());
+      return Ok(());
+    }
+    cli::Command::Fetch(options) => {
+      if let Some(error) = config_error {
+        eprintln!("sydney: {error}");
       }
-      "--help" | "-h" => {
-        println!(
-          r#"usage: {} [option, capsule_uri]
-Options:
-    --version, -v    show version text
-    --help, -h       show help text
-
-Sample invocations:
-    {0} gemini://fuwn.me/
-    {0} fuwn.me
-    {0} --help
-
-Report bugs to https://github.com/gemrest/sydney/issues"#,
-          args
-            .next()
-            .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string())
-        );
-
-        return Ok(());
+
+      std::process::exit(dump::main(&options, &config));
+    }
+    cli::Command::BookmarksList => {
+      for bookmark in bookmarks::load() {
+        println!("{bookmark}");
       }
-      _ => {
-        app.url = Url::parse(&url::prefix_gemini(&arg))?;
 
-        app.make_request();
+      return Ok(());
+    }
+    cli::Command::BookmarksAdd(location) => {
+      if let Err(error) = url::parse_location(&location)
+        .and_then(|url| bookmarks::add(url.as_str()))
+      {
+        eprintln!("sydney: {error}");
+
+        std::process::exit(1);
       }
+
+      return Ok(());
     }
+    cli::Command::Browse(locations) => locations,
+  };
+  let mut locations = locations;
+
+  if locations.is_empty() && !std::io::stdin().is_terminal() {
+    locations.push("-".to_string());
   }
 
-  terminal::enable_raw_mode()?;
+  let reads_stdin = locations.iter().any(|location| location == "-");
 
-  let mut stdout = std::io::stdout();
+  if cli.base.is_some() && !reads_stdin {
+    eprintln!(
+      "sydney: --base can only be used when reading from standard input"
+    );
+
+    std::process::exit(2);
+  }
 
-  match germ::request::request(
-    &Url::parse("gemini://fuwn.me/api/sydney/version").unwrap(),
-  ) {
-    Ok(response) =>
-      if let Some(content) = response.content() {
-        let content = content.trim();
-
-        if content > &String::from(env!("CARGO_PKG_VERSION")) {
-          app.error = Some(format!(
-            "Your Sydney version ({}) is outdated. It is recommended that you \
-             update to the newest version ({}).",
-            env!("CARGO_PKG_VERSION"),
-            content,
-          ));
-        }
+  let urls = locations
+    .iter()
+    .map(|location| {
+      if location == "-" {
+        ::url::Url::parse(stdin::URL).map_err(|error| error.to_string())
       } else {
-        app.error = Some(
-          "Could not check if Sydney has a newer version because the response \
-           had no content. Please try again later."
-            .to_string(),
-        );
-      },
-    Err(error) =>
-      app.error = Some(format!(
-        "Could not check if Sydney has a newer version: {}",
-        error
-      )),
+        url::parse_location(location)
+      }
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap_or_else(|error| {
+      eprintln!("sydney: {error}");
+
+      std::process::exit(2);
+    });
+  let mut app = App::new(config);
+
+  if reads_stdin {
+    app.set_stdin(stdin::read()?, cli.base);
   }
 
-  execute!(
-    stdout,
-    terminal::EnterAlternateScreen,
-    event::EnableMouseCapture
-  )?;
+  if let Some(wrap) = cli.wrap {
+    app.set_wrap_at(wrap);
+  }
+
+  if let Some(error) = config_error {
+    app.set_error(error);
+  }
+
+  if urls.is_empty() {
+    app.start();
+  } else {
+    app.restore(0, urls);
+  }
+
+  terminal::enable_raw_mode()?;
+
+  let mut stdout = std::io::stdout();
+
+  app.check_for_updates();
+
+  execute!(stdout, terminal::EnterAlternateScreen)?;
+
+  let mouse = app.config().mouse;
+
+  if mouse {
+    execute!(stdout, event::EnableMouseCapture)?;
+  }
 
   let mut terminal =
     ratatui::Terminal::new(ratatui::backend::CrosstermBackend::new(stdout))?;
-  let result =
-    App::run(&mut terminal, app, std::time::Duration::from_millis(250));
+  let result = App::run(
+    &mut terminal,
+    &mut app,
+    std::time::Duration::from_millis(250),
+  );
 
   terminal::disable_raw_mode()?;
-  execute!(
-    terminal.backend_mut(),
-    terminal::LeaveAlternateScreen,
-    event::DisableMouseCapture
-  )?;
+  execute!(terminal.backend_mut(), terminal::LeaveAlternateScreen)?;
+
+  if mouse {
+    execute!(terminal.backend_mut(), event::DisableMouseCapture)?;
+  }
   terminal.show_cursor()?;
 
   if let Err(err) = result {
-    println!("{:?}", err);
+    println!("{err:?}");
+  }
+
+  if let Err(error) = app.save_session() {
+    eprintln!("sydney: Could not save the session: {error}");
   }
 
   Ok(())
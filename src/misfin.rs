@@ -0,0 +1,190 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Misfin, mail for Gemini
+//!
+//! A message is sent to an address like `misfin://alice@example.com` over
+//! TLS, presenting the identity of the `misfin_identity` configuration key,
+//! which is who the message is from. Messages which were delivered are appended
+//! to the `sent.gmi` file of Sydney's data directory, which is shown as
+//! `about:sent`.
+
+use std::{
+  io::Write,
+  path::PathBuf,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use url::Url;
+
+use crate::{config::Config, request::Response};
+
+const DEFAULT_PORT: u16 = 1958;
+/// The largest request a server has to accept, address included
+const MAX_REQUEST: usize = 2048;
+
+/// Parse an address, with or without the `misfin://` prefix
+pub fn address(address: &str) -> Result<Url, String> {
+  let url = if address.starts_with("misfin://") {
+    address.to_string()
+  } else {
+    format!("misfin://{address}")
+  };
+
+  Url::parse(&url)
+    .ok()
+    .filter(|url| !url.username().is_empty() && url.host_str().is_some())
+    .ok_or_else(|| format!("\"{address}\" is not a valid Misfin address"))
+}
+
+/// The address of `url` without its scheme, e.g., `alice@example.com`
+pub fn mailbox(url: &Url) -> String {
+  format!("{}@{}", url.username(), url.host_str().unwrap_or_default())
+}
+
+/// Send `message` to `url`, returning the server's response
+pub fn send(
+  url: &Url,
+  message: &str,
+  config: &Config,
+) -> Result<Response, String> {
+  let identity = crate::identity::load(
+    config.misfin_identity.as_deref().ok_or_else(|| {
+      "Sending mail requires an identity, set one with the misfin_identity \
+       configuration key"
+        .to_string()
+    })?,
+  )?;
+  let address = format!("misfin://{}", mailbox(url));
+  let request = format!("{address} {}\r\n", body(message));
+
+  if request.len() > MAX_REQUEST {
+    return Err(format!(
+      "The message is {} bytes too long",
+      request.len() - MAX_REQUEST
+    ));
+  }
+
+  crate::gemini::exchange(
+    url,
+    url.port().unwrap_or(DEFAULT_PORT),
+    request.as_bytes(),
+    Some(&identity),
+  )
+}
+
+/// The body of `message` as it is sent, with its lines separated by line feeds
+/// so that only the end of the request is a carriage return and line feed
+fn body(message: &str) -> String {
+  message.trim_end().replace("\r\n", "\n").replace('\r', "\n")
+}
+
+fn path() -> Option<PathBuf> {
+  crate::config::data_directory().map(|directory| directory.join("sent.gmi"))
+}
+
+/// Record a delivered message in the sent log
+pub fn log(url: &Url, message: &str) -> Result<(), String> {
+  let path =
+    path().ok_or_else(|| "Could not locate the data directory".to_string())?;
+  let mut entry = format!("## To {} on {}\n\n```\n", mailbox(url), now());
+
+  for line in message.trim_end().lines() {
+    // Keep the message from ending its preformatted block early
+    if line.starts_with("```") {
+      entry.push(' ');
+    }
+
+    entry.push_str(line);
+    entry.push('\n');
+  }
+
+  entry.push_str("```\n\n");
+
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+  }
+
+  std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+    .and_then(|mut file| file.write_all(entry.as_bytes()))
+    .map_err(|error| error.to_string())
+}
+
+/// The Gemtext of `about:sent`
+pub fn sent() -> String {
+  let log = path()
+    .and_then(|path| std::fs::read_to_string(path).ok())
+    .unwrap_or_default();
+
+  if log.is_empty() {
+    "# Sent Messages\n\nNo messages have been sent yet.\n".to_string()
+  } else {
+    format!("# Sent Messages\n\n{log}")
+  }
+}
+
+/// The current time in UTC, e.g., `2024-01-31 12:00 UTC`
+fn now() -> String {
+  let seconds = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |duration| duration.as_secs());
+  let days = i64::try_from(seconds / 86_400).unwrap_or_default();
+  let minutes = seconds % 86_400 / 60;
+  // Howard Hinnant's conversion from days since the epoch to a civil date
+  let days = days + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+    - day_of_era / 146_096)
+    / 365;
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+  format!(
+    "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+    minutes / 60,
+    minutes % 60
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn separates_lines_with_line_feeds() {
+    for (message, expected) in [
+      ("Hello\n", "Hello"),
+      ("Hello\r\nthere\r\n", "Hello\nthere"),
+      ("Hello\rthere", "Hello\nthere"),
+      ("Hello\n\r\nthere", "Hello\n\nthere"),
+    ] {
+      assert_eq!(body(message), expected, "{message:?}");
+    }
+  }
+}
@@ -0,0 +1,99 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A Nex client
+//!
+//! Nex serves directory listings, whose paths end with `/`, and plain text
+//! documents. Listings are plain text too, except for their `=> ` lines which
+//! link to other documents, so they are converted to Gemtext with everything
+//! but those lines kept as text.
+
+use std::io::{Read, Write};
+
+use url::Url;
+
+use crate::request::Response;
+
+const DEFAULT_PORT: u16 = 1900;
+
+/// Request `url`, a `nex://host[:port]/path` URL
+pub fn request(url: &Url) -> Result<Response, String> {
+  let host = url.host_str().ok_or_else(|| format!("{url} has no host"))?;
+  let path = if url.path().is_empty() {
+    "/"
+  } else {
+    url.path()
+  };
+  let mut stream =
+    crate::request::connect(host, url.port().unwrap_or(DEFAULT_PORT))?;
+  let mut line = crate::url::percent_decode(path);
+  let mut body = vec![];
+
+  line.push(b'\n');
+
+  stream
+    .write_all(&line)
+    .and_then(|()| stream.read_to_end(&mut body))
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  let text = String::from_utf8_lossy(&body).replace("\r\n", "\n");
+
+  Ok(if path.ends_with('/') {
+    Response::success("text/gemini", listing(&text))
+  } else {
+    Response::success("text/plain", text)
+  })
+}
+
+/// Convert a directory listing to Gemtext
+fn listing(listing: &str) -> String {
+  let mut gemtext = String::new();
+
+  for line in listing.lines() {
+    if line.starts_with("=>") {
+      gemtext.push_str(line);
+      gemtext.push('\n');
+    } else {
+      crate::document::push_text(&mut gemtext, line);
+    }
+  }
+
+  gemtext
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn converts_listings() {
+    for (line, expected) in [
+      ("Welcome to my Nex site", "Welcome to my Nex site"),
+      ("=> about.txt", "=> about.txt"),
+      ("=> phlog/ Phlog", "=> phlog/ Phlog"),
+      ("# Not a heading", " # Not a heading"),
+      ("* Not a list item", " * Not a list item"),
+      ("> Not a quote", " > Not a quote"),
+      ("```", " ```"),
+      ("=: Not a prompt", " =: Not a prompt"),
+      ("  # Indented", "  # Indented"),
+      ("=> phlog/\r\n# Phlog", "=> phlog/\n # Phlog"),
+    ] {
+      assert_eq!(listing(line), format!("{expected}\n"), "{line:?}");
+    }
+  }
+}
@@ -0,0 +1,249 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Responses, and what fetching them has in common across schemes
+
+use std::{
+  fmt::Write,
+  net::{TcpStream, ToSocketAddrs},
+  path::Path,
+  time::Duration,
+};
+
+use url::Url;
+
+/// How long connecting, and each read or write, may take
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A response in the shape of a Gemini response, which every scheme Sydney
+/// supports is mapped to
+#[derive(Clone)]
+pub struct Response {
+  /// The two digit status code, e.g., `20` for success
+  pub status: u8,
+  /// The MIME type of a successful response, or a message or URL depending
+  /// on the status
+  pub meta:   String,
+  /// The body of a successful response, empty otherwise
+  pub body:   Vec<u8>,
+}
+impl Response {
+  /// A successful response of type `mime`
+  pub fn success(mime: &str, body: impl Into<Vec<u8>>) -> Self {
+    Self {
+      status: 20,
+      meta:   mime.to_string(),
+      body:   body.into(),
+    }
+  }
+
+  /// Parse a raw Gemini response, a `<status> <meta>\r\n` header followed by
+  /// the body
+  ///
+  /// # Errors
+  ///
+  /// If the header is missing or its status is not a valid status code.
+  pub fn parse(data: &[u8]) -> Result<Self, String> {
+    let end = data
+      .iter()
+      .position(|byte| *byte == b'\n')
+      .ok_or_else(|| "The response has no header".to_string())?;
+    let header = String::from_utf8_lossy(&data[..end]);
+    let header = header.trim_end_matches('\r');
+    let (status, meta) = header.split_at(header.len().min(2));
+
+    Ok(Self {
+      status: status
+        .parse()
+        .ok()
+        .filter(|status| (10..70).contains(status))
+        .ok_or_else(|| {
+          format!("\"{header}\" is not a valid response header")
+        })?,
+      meta:   meta.trim().to_string(),
+      body:   data[end + 1..].to_vec(),
+    })
+  }
+
+  /// Whether the server asks for input to send back as the query
+  #[must_use]
+  pub const fn is_input(&self) -> bool { self.status / 10 == 1 }
+
+  /// Whether the response carries a document
+  #[must_use]
+  pub const fn is_success(&self) -> bool { self.status / 10 == 2 }
+
+  /// Whether the response redirects to the URL in `meta`
+  #[must_use]
+  pub const fn is_redirect(&self) -> bool { self.status / 10 == 3 }
+
+  /// The MIME type of a successful response, without its parameters
+  #[must_use]
+  pub fn mime(&self) -> String {
+    let mime = self.meta.split(';').next().unwrap_or_default().trim();
+
+    if mime.is_empty() {
+      "text/gemini".to_string()
+    } else {
+      mime.to_ascii_lowercase()
+    }
+  }
+
+  /// Whether the body can be displayed as text
+  #[must_use]
+  pub fn is_text(&self) -> bool { self.mime().starts_with("text/") }
+
+  /// The body as text, replacing invalid UTF-8
+  #[must_use]
+  pub fn text(&self) -> String {
+    String::from_utf8_lossy(&self.body).into_owned()
+  }
+}
+
+/// Read a local file, or list a local directory
+///
+/// # Errors
+///
+/// If `url` is not a path or could not be read.
+pub fn file(url: &Url) -> Result<Response, String> {
+  let path = url
+    .to_file_path()
+    .map_err(|()| format!("{url} is not a valid file path"))?;
+
+  if path.is_dir() {
+    if !url.path().ends_with('/') {
+      return Ok(Response {
+        status: 31,
+        meta:   format!("{}/", url.path()),
+        body:   vec![],
+      });
+    }
+
+    return directory(&path)
+      .map(|listing| Response::success("text/gemini", listing));
+  }
+
+  std::fs::read(&path)
+    .map(|body| {
+      Response::success(
+        crate::titan::mime(url.path()).unwrap_or("application/octet-stream"),
+        body,
+      )
+    })
+    .map_err(|error| format!("Could not read {}: {error}", path.display()))
+}
+
+/// A Gemtext listing of the entries of a directory, directories first
+fn directory(path: &Path) -> Result<String, String> {
+  let mut entries = std::fs::read_dir(path)
+    .map_err(|error| format!("Could not read {}: {error}", path.display()))?
+    .filter_map(Result::ok)
+    .map(|entry| {
+      (
+        !entry.path().is_dir(),
+        entry.file_name().to_string_lossy().to_string(),
+        entry.path(),
+      )
+    })
+    .collect::<Vec<_>>();
+  let mut listing = format!("# {}\n\n", path.display());
+
+  entries.sort();
+
+  if let Some(parent) = path.parent() {
+    if let Ok(url) = Url::from_directory_path(parent) {
+      let _ = writeln!(listing, "=> {url} ../");
+    }
+  }
+
+  for (file, name, path) in entries {
+    let url = if file {
+      Url::from_file_path(&path)
+    } else {
+      Url::from_directory_path(&path)
+    };
+
+    if let Ok(url) = url {
+      let _ =
+        writeln!(listing, "=> {url} {name}{}", if file { "" } else { "/" });
+    }
+  }
+
+  Ok(listing)
+}
+
+/// Open a TCP connection to `host`, trying each of its addresses in turn
+///
+/// # Errors
+///
+/// If `host` could not be resolved or none of its addresses could be
+/// connected to.
+pub fn connect(host: &str, port: u16) -> Result<TcpStream, String> {
+  let mut last_error = format!("Could not resolve {host}");
+
+  for address in (host, port)
+    .to_socket_addrs()
+    .map_err(|error| format!("Could not resolve {host}: {error}"))?
+  {
+    match TcpStream::connect_timeout(&address, TIMEOUT) {
+      Ok(stream) => {
+        stream
+          .set_read_timeout(Some(TIMEOUT))
+          .and_then(|()| stream.set_write_timeout(Some(TIMEOUT)))
+          .map_err(|error| error.to_string())?;
+
+        return Ok(stream);
+      }
+      Err(error) =>
+        last_error = format!("Could not connect to {host}: {error}"),
+    }
+  }
+
+  Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_files_by_extension() {
+    let directory = std::env::temp_dir()
+      .join(format!("sydney-request-{}", std::process::id()));
+
+    std::fs::create_dir_all(&directory).unwrap();
+
+    for (name, mime) in [
+      ("page.gmi", "text/gemini"),
+      ("page.gemini", "text/gemini"),
+      ("notes.txt", "text/plain"),
+      ("image.png", "application/octet-stream"),
+      ("README", "application/octet-stream"),
+    ] {
+      let path = directory.join(name);
+
+      std::fs::write(&path, "# Content").unwrap();
+
+      let response = file(&Url::from_file_path(&path).unwrap()).unwrap();
+
+      assert_eq!(response.meta, mime, "{name:?}");
+      assert_eq!(response.body, b"# Content", "{name:?}");
+    }
+
+    std::fs::remove_dir_all(&directory).unwrap();
+  }
+}
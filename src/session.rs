@@ -0,0 +1,102 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The tabs open when Sydney last quit, stored in the `session` file of
+//! Sydney's data directory as the index of the displayed tab followed by the
+//! URL of every tab, one per line
+
+use std::path::PathBuf;
+
+use url::Url;
+
+fn path() -> Option<PathBuf> {
+  crate::config::data_directory().map(|directory| directory.join("session"))
+}
+
+/// Save the tabs showing `urls`, of which the tab at `current` is displayed
+pub fn save(current: usize, urls: &[&Url]) -> Result<(), String> {
+  let path =
+    path().ok_or_else(|| "Could not locate the data directory".to_string())?;
+
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+  }
+
+  std::fs::write(path, serialize(current, urls))
+    .map_err(|error| error.to_string())
+}
+
+/// The content of the session file, leaving out documents read from
+/// standard input, which cannot be reopened
+fn serialize(current: usize, urls: &[&Url]) -> String {
+  let kept = urls
+    .iter()
+    .enumerate()
+    .filter(|(_, url)| url.as_str() != crate::stdin::URL)
+    .collect::<Vec<_>>();
+  let current = kept
+    .iter()
+    .filter(|(index, _)| *index < current)
+    .count()
+    .min(kept.len().saturating_sub(1));
+
+  kept
+    .iter()
+    .fold(format!("{current}\n"), |session, (_, url)| {
+      format!("{session}{url}\n")
+    })
+}
+
+/// The index of the displayed tab and the URL of every tab of the last
+/// session
+pub fn load() -> Option<(usize, Vec<Url>)> {
+  let content = std::fs::read_to_string(path()?).ok()?;
+  let mut lines = content.lines();
+  let current = lines.next()?.trim().parse().ok()?;
+  let urls = lines
+    .filter_map(|line| Url::parse(line.trim()).ok())
+    .collect::<Vec<_>>();
+
+  if urls.is_empty() {
+    None
+  } else {
+    Some((current, urls))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn serializes_tabs_without_standard_input() {
+    let urls = ["gemini://a/", "about:stdin", "gemini://b/", "about:stdin"]
+      .map(|url| Url::parse(url).unwrap());
+    let urls = urls.iter().collect::<Vec<_>>();
+
+    for (current, expected) in [
+      (0, "0\ngemini://a/\ngemini://b/\n"),
+      (1, "1\ngemini://a/\ngemini://b/\n"),
+      (2, "1\ngemini://a/\ngemini://b/\n"),
+      (3, "1\ngemini://a/\ngemini://b/\n"),
+    ] {
+      assert_eq!(serialize(current, &urls), expected, "{current}");
+    }
+
+    assert_eq!(serialize(0, &urls[1..2]), "0\n");
+  }
+}
@@ -0,0 +1,84 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! A Spartan client
+//!
+//! Spartan has no input responses, data is uploaded alongside the request
+//! instead. Sydney sends the decoded query of a URL as that data, so `=:`
+//! prompt lines can ask for it through the same prompt as Gemini's input
+//! responses.
+
+use std::io::{Read, Write};
+
+use url::Url;
+
+use crate::request::Response;
+
+const DEFAULT_PORT: u16 = 300;
+
+/// Request `url`, uploading its decoded query as the request's data
+pub fn request(url: &Url) -> Result<Response, String> {
+  let host = url.host_str().ok_or_else(|| format!("{url} has no host"))?;
+  let path = if url.path().is_empty() {
+    "/"
+  } else {
+    url.path()
+  };
+  let data = url
+    .query()
+    .map(crate::url::percent_decode)
+    .unwrap_or_default();
+  let mut stream =
+    crate::request::connect(host, url.port().unwrap_or(DEFAULT_PORT))?;
+  let mut request = format!("{host} {path} {}\r\n", data.len()).into_bytes();
+  let mut response = vec![];
+
+  request.extend(data);
+
+  stream
+    .write_all(&request)
+    .and_then(|()| stream.read_to_end(&mut response))
+    .map_err(|error| format!("Could not request {url}: {error}"))?;
+
+  parse(&response)
+}
+
+/// Parse a raw Spartan response, mapping its single digit status to the
+/// Gemini status which means the same
+fn parse(data: &[u8]) -> Result<Response, String> {
+  let end = data
+    .iter()
+    .position(|byte| *byte == b'\n')
+    .ok_or_else(|| "The response has no header".to_string())?;
+  let header = String::from_utf8_lossy(&data[..end]);
+  let header = header.trim_end_matches('\r');
+  let (status, meta) = header.split_once(' ').unwrap_or((header, ""));
+
+  Ok(Response {
+    status: match status {
+      "2" => 20,
+      "3" => 30,
+      // A client error, i.e., a bad request
+      "4" => 59,
+      // A server error, which may be temporary
+      "5" => 40,
+      _ => return Err(format!("\"{header}\" is not a valid response header")),
+    },
+    meta:   meta.trim().to_string(),
+    body:   data[end + 1..].to_vec(),
+  })
+}
@@ -35,6 +35,10 @@ impl<T> StatefulList<T> {
   }
 
   pub fn next(&mut self) {
+    if self.items.is_empty() {
+      return;
+    }
+
     let i = match self.state.selected() {
       Some(i) =>
         if i >= self.items.len() - 1 {
@@ -51,6 +55,10 @@ impl<T> StatefulList<T> {
   }
 
   pub fn previous(&mut self) {
+    if self.items.is_empty() {
+      return;
+    }
+
     let i = match self.state.selected() {
       Some(i) =>
         if i == 0 {
@@ -66,7 +74,34 @@ impl<T> StatefulList<T> {
     self.state.select(Some(i));
   }
 
+  pub fn scroll_down(&mut self, amount: usize) {
+    if self.items.is_empty() {
+      return;
+    }
+
+    self.select(
+      self
+        .state
+        .selected()
+        .map_or(0, |i| (i + amount).min(self.items.len() - 1)),
+    );
+  }
+
+  pub fn scroll_up(&mut self, amount: usize) {
+    if self.items.is_empty() {
+      return;
+    }
+
+    self.select(
+      self.state.selected().map_or(0, |i| i.saturating_sub(amount)),
+    );
+  }
+
   pub fn last(&mut self) {
+    if self.items.is_empty() {
+      return;
+    }
+
     self.state.select(Some(self.items.len() - 1));
 
     self.selected = self.items.len() - 1;
@@ -78,5 +113,28 @@ impl<T> StatefulList<T> {
     self.selected = 0;
   }
 
+  pub fn select(&mut self, i: usize) {
+    self.state.select(Some(i));
+
+    self.selected = i;
+  }
+
   pub fn unselect(&mut self) { self.state.select(None); }
+
+  /// Restore a selection and scroll position saved from before the items
+  /// were replaced, clamped to the new items
+  pub fn restore(&mut self, state: &ListState) {
+    let last = self.items.len().checked_sub(1);
+
+    *self.state.offset_mut() = last.map_or(0, |last| state.offset().min(last));
+
+    self.state.select(
+      state
+        .selected()
+        .zip(last)
+        .map(|(selected, last)| selected.min(last)),
+    );
+
+    self.selected = self.state.selected().unwrap_or(0);
+  }
 }
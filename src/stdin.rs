@@ -0,0 +1,38 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Documents piped into Sydney through standard input
+
+use std::io::Read;
+
+/// The URL a document read from standard input is displayed under
+pub const URL: &str = "about:stdin";
+
+/// Read all of standard input, replacing invalid UTF-8
+///
+/// # Errors
+///
+/// If standard input could not be read.
+pub fn read() -> Result<String, String> {
+  let mut content = vec![];
+
+  std::io::stdin()
+    .read_to_end(&mut content)
+    .map_err(|error| format!("Could not read standard input: {error}"))?;
+
+  Ok(String::from_utf8_lossy(&content).into_owned())
+}
@@ -0,0 +1,51 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use url::Url;
+
+use crate::{document::Item, stateful_list::StatefulList};
+
+/// The page state of a tab which is not currently being displayed
+///
+/// The state of the displayed tab lives directly on `App` and is swapped in
+/// and out of its `Tab` slot as tabs are switched.
+pub struct Tab {
+  pub url:              Url,
+  pub items:            StatefulList<Item>,
+  pub capsule_history:  Vec<Url>,
+  pub previous_capsule: Option<Url>,
+  pub source:           String,
+  /// Whether the page has been requested yet, tabs opened in the background
+  /// are only requested once they are first switched to
+  pub loaded:           bool,
+}
+impl Tab {
+  pub fn new(url: Url) -> Self {
+    Self {
+      url,
+      items: StatefulList::with_items(Vec::new()),
+      capsule_history: vec![],
+      previous_capsule: None,
+      source: String::new(),
+      loaded: false,
+    }
+  }
+}
+
+pub fn title(url: &Url) -> String {
+  format!("{}{}", url.host_str().unwrap_or_default(), url.path())
+}
@@ -0,0 +1,129 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The built-in colour themes
+
+use ratatui::style::Color;
+
+/// A built-in colour theme
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+  /// Light text on a dark background
+  Dark,
+  /// Dark text on a light background
+  Light,
+  /// The terminal's own foreground and background colours
+  Terminal,
+}
+
+/// The colours a theme paints the interface with
+pub struct Palette {
+  /// Text
+  pub foreground:           Color,
+  /// Behind text
+  pub background:           Color,
+  /// Gemtext line markers, link targets and other highlights
+  pub accent:               Color,
+  /// The selected item, the current tab and the status bar
+  pub selection_foreground: Color,
+  /// Behind the selected item, the current tab and the status bar
+  pub selection_background: Color,
+  /// Items covered by a visual line selection
+  pub visual:               Color,
+  /// Items which changed when the page was last reloaded
+  pub changed:              Color,
+  /// Behind errors in the status bar
+  pub error:                Color,
+  /// Behind dialogs
+  pub dialog:               Color,
+}
+
+impl Theme {
+  /// The name of every theme, as accepted by [`Theme::parse`]
+  pub const NAMES: &'static [&'static str] = &["dark", "light", "terminal"];
+
+  /// The theme called `name`
+  ///
+  /// # Errors
+  ///
+  /// If `name` is not a built-in theme.
+  pub fn parse(name: &str) -> Result<Self, String> {
+    match name {
+      "dark" => Ok(Self::Dark),
+      "light" => Ok(Self::Light),
+      "terminal" => Ok(Self::Terminal),
+      _ =>
+        Err(format!(
+          "\"{name}\" is not a valid theme, expected one of {}",
+          Self::NAMES.join(", ")
+        )),
+    }
+  }
+
+  /// The name of the theme
+  #[must_use]
+  pub const fn name(self) -> &'static str {
+    match self {
+      Self::Dark => "dark",
+      Self::Light => "light",
+      Self::Terminal => "terminal",
+    }
+  }
+
+  /// The colours of the theme
+  #[must_use]
+  pub const fn palette(self) -> Palette {
+    match self {
+      Self::Dark =>
+        Palette {
+          foreground:           Color::White,
+          background:           Color::Black,
+          accent:               Color::LightBlue,
+          selection_foreground: Color::Black,
+          selection_background: Color::White,
+          visual:               Color::DarkGray,
+          changed:              Color::Indexed(22),
+          error:                Color::Red,
+          dialog:               Color::Cyan,
+        },
+      Self::Light =>
+        Palette {
+          foreground:           Color::Black,
+          background:           Color::White,
+          accent:               Color::Blue,
+          selection_foreground: Color::White,
+          selection_background: Color::Black,
+          visual:               Color::Gray,
+          changed:              Color::Indexed(194),
+          error:                Color::LightRed,
+          dialog:               Color::LightCyan,
+        },
+      Self::Terminal =>
+        Palette {
+          foreground:           Color::Reset,
+          background:           Color::Reset,
+          accent:               Color::Blue,
+          selection_foreground: Color::Black,
+          selection_background: Color::Gray,
+          visual:               Color::DarkGray,
+          changed:              Color::Indexed(22),
+          error:                Color::Red,
+          dialog:               Color::Cyan,
+        },
+    }
+  }
+}
@@ -0,0 +1,102 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Titan, Gemini's companion protocol for uploading pages
+//!
+//! A Titan URL names the page to upload alongside parameters describing the
+//! upload, e.g., `titan://example.com/page.gmi;size=5;mime=text/gemini`. A
+//! `token` parameter given by the capsule, e.g., a password, is passed along
+//! as is.
+
+use url::Url;
+
+use crate::{config::Config, request::Response};
+
+/// The Gemini URL of the page `url` uploads to, dropping its Titan parameters
+pub fn page(url: &Url) -> Result<Url, String> {
+  let mut page = url.clone();
+
+  page
+    .set_scheme("gemini")
+    .map_err(|()| format!("{url} cannot be edited over Titan"))?;
+  page.set_path(split(url).0);
+  page.set_query(None);
+  page.set_fragment(None);
+
+  Ok(page)
+}
+
+/// Upload `body` to `url`, a Gemini or Titan URL, presenting the configured
+/// identity
+pub fn upload(
+  url: &Url,
+  body: &[u8],
+  config: &Config,
+) -> Result<Response, String> {
+  let (path, token) = split(url);
+  let mut target = page(url)?;
+
+  target
+    .set_scheme("titan")
+    .map_err(|()| format!("{url} cannot be edited over Titan"))?;
+  target.set_path(&format!(
+    "{path};size={};mime={}{}",
+    body.len(),
+    mime(path).unwrap_or("text/gemini"),
+    token
+      .map(|token| format!(";token={token}"))
+      .unwrap_or_default()
+  ));
+
+  let mut request = format!("{target}\r\n").into_bytes();
+
+  request.extend(body);
+
+  crate::gemini::exchange(
+    &target,
+    target.port().unwrap_or(crate::gemini::DEFAULT_PORT),
+    &request,
+    crate::identity::configured(config, &target)?.as_ref(),
+  )
+}
+
+/// Split the path of `url` from the token among its Titan parameters, Gemini
+/// URLs have no parameters
+fn split(url: &Url) -> (&str, Option<&str>) {
+  if url.scheme() != "titan" {
+    return (url.path(), None);
+  }
+
+  let mut parts = url.path().split(';');
+
+  (
+    parts.next().unwrap_or_default(),
+    parts.find_map(|parameter| parameter.strip_prefix("token=")),
+  )
+}
+
+/// Guess the MIME type of a page from the extension of its path, if it is
+/// one Sydney knows
+pub fn mime(path: &str) -> Option<&'static str> {
+  match path.rsplit_once('.').map(|(_, extension)| extension) {
+    Some("gmi" | "gemini") => Some("text/gemini"),
+    Some("txt") => Some("text/plain"),
+    Some("md") => Some("text/markdown"),
+    Some("html" | "htm") => Some("text/html"),
+    _ => None,
+  }
+}
@@ -0,0 +1,125 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use germ::ast::Node;
+
+use crate::document::Item;
+
+pub struct Heading {
+  pub index: usize,
+  pub level: usize,
+  pub text:  String,
+}
+
+/// Collect every heading of the current page along with the index of the
+/// item it belongs to
+pub fn headings(items: &[Item]) -> Vec<Heading> {
+  items
+    .iter()
+    .enumerate()
+    .filter(|(_, item)| !item.preformatted)
+    .flat_map(|(index, item)| {
+      item.nodes.iter().filter_map(move |node| {
+        if let Node::Heading { level, text } = node {
+          Some(Heading {
+            index,
+            level: *level,
+            text: text.clone(),
+          })
+        } else {
+          None
+        }
+      })
+    })
+    .collect()
+}
+
+/// The heading whose section contains the item at `selected`
+pub fn current(headings: &[Heading], selected: usize) -> Option<usize> {
+  headings.iter().rposition(|heading| heading.index <= selected)
+}
+
+/// The first heading after the item at `selected`, or the first heading of
+/// the page if nothing is selected
+pub fn next(headings: &[Heading], selected: Option<usize>) -> Option<usize> {
+  headings
+    .iter()
+    .find(|heading| selected.is_none_or(|selected| heading.index > selected))
+    .map(|heading| heading.index)
+}
+
+/// The last heading before the item at `selected`
+pub fn previous(
+  headings: &[Heading],
+  selected: Option<usize>,
+) -> Option<usize> {
+  let selected = selected?;
+
+  headings
+    .iter()
+    .rev()
+    .find(|heading| heading.index < selected)
+    .map(|heading| heading.index)
+}
+
+/// Find the heading which best matches `query`, where every character of
+/// `query` must appear in order within the heading text
+pub fn find(headings: &[Heading], query: &str) -> Option<usize> {
+  headings
+    .iter()
+    .filter_map(|heading| {
+      fuzzy_score(&heading.text, query).map(|score| (score, heading.index))
+    })
+    .max_by_key(|(score, index)| (*score, std::cmp::Reverse(*index)))
+    .map(|(_, index)| index)
+}
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence,
+/// favouring consecutive matches and matches at the start of words
+fn fuzzy_score(text: &str, query: &str) -> Option<usize> {
+  let text = text.to_lowercase().chars().collect::<Vec<_>>();
+  let mut score = 0;
+  let mut position = 0;
+  let mut previous_match: Option<usize> = None;
+
+  for query_character in query.to_lowercase().chars() {
+    if query_character.is_whitespace() {
+      continue;
+    }
+
+    let offset = text
+      .get(position..)?
+      .iter()
+      .position(|character| *character == query_character)?;
+    let matched = position + offset;
+
+    score += 1;
+
+    if previous_match.is_some_and(|previous| previous + 1 == matched) {
+      score += 5;
+    }
+
+    if matched == 0 || !text[matched - 1].is_alphanumeric() {
+      score += 3;
+    }
+
+    previous_match = Some(matched);
+    position = matched + 1;
+  }
+
+  Some(score)
+}
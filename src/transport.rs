@@ -0,0 +1,297 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Transports fetch the URLs of a scheme, and are looked up by scheme in a
+//! [`Registry`], so that a protocol is supported by registering a transport
+//! for it
+
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::{config::Config, request::Response};
+
+/// How many redirects are followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// A way of fetching URLs of a scheme
+///
+/// Any `Fn(&Url, &Config) -> Result<Response, String>` is a transport.
+/// Responses carry their whole body rather than a stream, as every protocol
+/// closes the connection after the body and pages are only rendered once
+/// they are complete.
+pub trait Transport {
+  /// Make a single request for `url`, without following redirects
+  ///
+  /// # Errors
+  ///
+  /// If no response could be received, e.g., the host is unreachable.
+  fn request(&self, url: &Url, config: &Config) -> Result<Response, String>;
+}
+impl<F> Transport for F
+where F: Fn(&Url, &Config) -> Result<Response, String>
+{
+  fn request(&self, url: &Url, config: &Config) -> Result<Response, String> {
+    self(url, config)
+  }
+}
+
+/// A transport which does not depend on the configuration
+fn stateless(request: fn(&Url) -> Result<Response, String>) -> impl Transport {
+  move |url: &Url, _: &Config| request(url)
+}
+
+/// The transport of every supported scheme
+pub struct Registry {
+  transports: HashMap<String, Box<dyn Transport>>,
+}
+impl Default for Registry {
+  fn default() -> Self {
+    let mut registry = Self::empty();
+
+    registry.register("about", |url: &Url, config: &Config| {
+      Ok(crate::about::page(config, url.path()).map_or_else(
+        || {
+          Response {
+            status: 51,
+            meta:   format!("{url} is not a known about page"),
+            body:   vec![],
+          }
+        },
+        |page| Response::success("text/gemini", page),
+      ))
+    });
+    registry.register("file", stateless(crate::request::file));
+    registry.register("finger", stateless(crate::finger::request));
+    registry.register("gemini", |url: &Url, config: &Config| {
+      crate::gemini::request(
+        url,
+        crate::identity::configured(config, url)?.as_ref(),
+      )
+    });
+    registry.register("gopher", stateless(crate::gopher::request));
+    registry.register("guppy", stateless(crate::guppy::request));
+    registry.register("misfin", |url: &Url, _: &Config| {
+      Err(format!("{url} is a mailbox, write to it with :misfin"))
+    });
+    registry.register("nex", stateless(crate::nex::request));
+    registry.register("spartan", stateless(crate::spartan::request));
+
+    registry
+  }
+}
+impl Registry {
+  /// A registry without any transports
+  #[must_use]
+  pub fn empty() -> Self {
+    Self {
+      transports: HashMap::new(),
+    }
+  }
+
+  /// Fetch URLs of `scheme` with `transport`, replacing any transport which
+  /// was registered for it before
+  pub fn register(
+    &mut self,
+    scheme: &str,
+    transport: impl Transport + 'static,
+  ) {
+    self
+      .transports
+      .insert(scheme.to_string(), Box::new(transport));
+  }
+
+  /// Fetch `url`, following redirects, returning the URL which was finally
+  /// fetched alongside its response
+  ///
+  /// # Errors
+  ///
+  /// If no transport is registered for the scheme of a URL, the transport
+  /// fails, or a redirect changes schemes or exceeds the redirect limit.
+  pub fn fetch(
+    &self,
+    url: &Url,
+    config: &Config,
+  ) -> Result<(Url, Response), String> {
+    let mut url = url.clone();
+
+    for _ in 0..=MAX_REDIRECTS {
+      let response = self
+        .transports
+        .get(url.scheme())
+        .ok_or_else(|| format!("The {} scheme is not supported", url.scheme()))?
+        .request(&url, config)?;
+
+      if !response.is_redirect() {
+        return Ok((url, response));
+      }
+
+      let target = url.join(&response.meta).map_err(|error| {
+        format!(
+          "Could not follow the redirect to {}: {error}",
+          response.meta
+        )
+      })?;
+
+      if target.scheme() != url.scheme() {
+        return Err(format!("Refusing to follow a redirect to {target}"));
+      }
+
+      url = target;
+    }
+
+    Err(format!("Too many redirects, stopped at {url}"))
+  }
+}
+
+/// A transport serving canned responses from memory, so that fetching can be
+/// tested without a network
+///
+/// URLs without a canned response are answered with a `51 Not found`.
+#[derive(Default)]
+pub struct Fake {
+  responses: HashMap<String, Response>,
+}
+impl Fake {
+  /// Answer requests for `url` with `response`
+  #[must_use]
+  pub fn serve(mut self, url: &str, response: Response) -> Self {
+    self.responses.insert(url.to_string(), response);
+
+    self
+  }
+}
+impl Transport for Fake {
+  fn request(&self, url: &Url, _: &Config) -> Result<Response, String> {
+    Ok(
+      self
+        .responses
+        .get(url.as_str())
+        .cloned()
+        .unwrap_or_else(|| {
+          Response {
+            status: 51,
+            meta:   "Not found".to_string(),
+            body:   vec![],
+          }
+        }),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn redirect(to: &str) -> Response {
+    Response {
+      status: 31,
+      meta:   to.to_string(),
+      body:   vec![],
+    }
+  }
+
+  fn registry(fake: Fake) -> Registry {
+    let mut registry = Registry::empty();
+
+    registry.register("gemini", fake);
+
+    registry
+  }
+
+  fn fetch(registry: &Registry, url: &str) -> Result<(Url, Response), String> {
+    registry.fetch(&Url::parse(url).unwrap(), &Config::default())
+  }
+
+  #[test]
+  fn fetches_with_the_transport_of_the_scheme() {
+    let registry = registry(
+      Fake::default()
+        .serve("gemini://fake/", Response::success("text/gemini", "# Fake")),
+    );
+    let (url, response) = fetch(&registry, "gemini://fake/").unwrap();
+
+    assert_eq!(url.as_str(), "gemini://fake/");
+    assert_eq!(response.status, 20);
+    assert_eq!(response.text(), "# Fake");
+    assert_eq!(
+      fetch(&registry, "gemini://fake/missing").unwrap().1.status,
+      51
+    );
+    assert_eq!(
+      fetch(&registry, "gopher://fake/").err().unwrap(),
+      "The gopher scheme is not supported"
+    );
+  }
+
+  #[test]
+  fn follows_redirects() {
+    let registry = registry(
+      Fake::default()
+        .serve("gemini://fake/old", redirect("new"))
+        .serve("gemini://fake/new", Response::success("text/plain", "new"))
+        .serve("gemini://fake/away", redirect("gopher://fake/"))
+        .serve("gemini://fake/loop", redirect("/loop")),
+    );
+    let (url, response) = fetch(&registry, "gemini://fake/old").unwrap();
+
+    assert_eq!(url.as_str(), "gemini://fake/new");
+    assert_eq!(response.text(), "new");
+    assert_eq!(
+      fetch(&registry, "gemini://fake/away").err().unwrap(),
+      "Refusing to follow a redirect to gopher://fake/"
+    );
+    assert_eq!(
+      fetch(&registry, "gemini://fake/loop").err().unwrap(),
+      "Too many redirects, stopped at gemini://fake/loop"
+    );
+  }
+
+  #[test]
+  fn app_requests_through_its_registry() {
+    let mut app = crate::App::new(Config::default());
+
+    app.transports = registry(
+      Fake::default()
+        .serve(
+          "gemini://fake/",
+          Response::success("text/gemini", "# Fake\n=> search Search"),
+        )
+        .serve(
+          "gemini://fake/search",
+          Response {
+            status: 10,
+            meta:   "Query".to_string(),
+            body:   vec![],
+          },
+        ),
+    );
+    app.url = Url::parse("gemini://fake/").unwrap();
+
+    app.make_request();
+
+    assert_eq!(app.source, "# Fake\n=> search Search");
+    assert_eq!(app.items.items.len(), 2);
+    assert_eq!(app.items.items[1].link.as_deref(), Some("search"));
+
+    app.follow_link("search");
+
+    assert!(app.accept_response_input);
+    assert_eq!(app.response_input_text, "Query");
+    assert_eq!(app.url.as_str(), "gemini://fake/search");
+  }
+}
@@ -15,17 +15,24 @@
 // Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+//! Drawing the interface, and rendering documents to styled text
+
 use germ::ast::Node;
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
-  text::{Line, Span},
+  style::{Modifier, Style},
+  text::{Line, Span, Text},
   widgets,
   widgets::{ListItem, Paragraph},
 };
+use unicode_width::UnicodeWidthChar;
+
+use crate::{document::Item, theme::Palette};
 
+/// Draw `app`: its tabs, the page, the status bar and any open dialog
 #[allow(clippy::too_many_lines)]
 pub fn ui(f: &mut ratatui::Frame<'_>, app: &mut crate::App) {
+  let palette = app.config.theme.palette();
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints(
@@ -38,187 +45,113 @@ pub fn ui(f: &mut ratatui::Frame<'_>, app: &mut crate::App) {
     )
     .split(f.size());
 
+  let page_area = if app.tabs.len() > 1 {
+    let tab_chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(1), Constraint::Min(0)])
+      .split(chunks[0]);
+
+    tabs(f, app, &palette, tab_chunks[0]);
+
+    tab_chunks[1]
+  } else {
+    chunks[0]
+  };
+  let visual_range = (app.input_mode == crate::input::Mode::Visual)
+    .then(|| app.visual_range());
   let items: Vec<ListItem<'_>> = app
     .items
     .items
     .iter()
-    .map(|(text_lines, _link, pre)| {
-      let mut lines = vec![];
-
-      for line in text_lines {
-        let mut line = line.clone();
-
-        if *pre {
-          if let Node::Text(text) = line {
-            line = Node::PreformattedText {
-              alt_text: None,
-              text: text.to_string(),
-            }
-          }
-        }
-
-        macro_rules! wrap_split {
-          ($text:ident, $lines:ident) => {
-            let wrappeds = $text
-              .as_bytes()
-              .chunks((app.wrap_at as usize) - 5)
-              .map(|buf| {
-                #[allow(unsafe_code)]
-                unsafe { std::str::from_utf8_unchecked(buf) }.to_string()
-              })
-              .collect::<Vec<_>>();
-
-            for (i, wrapped) in wrappeds.iter().enumerate() {
-              $lines.push(Line::from(format!("    {}{}", wrapped, {
-                if i < wrappeds.len() - 1 && wrappeds.len() != 1 {
-                  "-"
-                } else {
-                  ""
-                }
-              })));
-            }
-          };
-        }
+    .enumerate()
+    .map(|(index, item)| {
+      let item = ListItem::new(render_item(item, app.wrap_at, &palette));
 
-        match line {
-          germ::ast::Node::Text(text) => {
-            if text != "sydney_abc_123" {
-              wrap_split!(text, lines);
-            }
-          }
-          germ::ast::Node::Blockquote(text) => {
-            let wrappeds = text
-              .as_bytes()
-              .chunks((app.wrap_at as usize) - 5)
-              .map(|buf| {
-                #[allow(unsafe_code)]
-                unsafe { std::str::from_utf8_unchecked(buf) }.to_string()
-              })
-              .collect::<Vec<_>>();
-
-            for (i, wrapped) in wrappeds.iter().enumerate() {
-              lines.push(Line::from(vec![
-                Span::styled("  > ", Style::default().fg(Color::LightBlue)),
-                Span::styled(
-                  format!("{}{}", wrapped.clone(), {
-                    if i < wrappeds.len() && wrappeds.len() != 1 {
-                      "-"
-                    } else {
-                      ""
-                    }
-                  }),
-                  Style::default().add_modifier(Modifier::ITALIC),
-                ),
-              ]));
-            }
-          }
-          germ::ast::Node::Link { to, text } => {
-            let mut span_list =
-              vec![Span::styled(" => ", Style::default().fg(Color::LightBlue))];
-
-            span_list.push(Span::styled(
-              text.unwrap_or_else(|| to.clone()),
-              Style::default().add_modifier(Modifier::UNDERLINED),
-            ));
-            span_list.push(Span::from(" "));
-            span_list
-              .push(Span::styled(to, Style::default().fg(Color::LightBlue)));
-
-            lines.push(Line::from(span_list));
-          }
-          germ::ast::Node::Heading { text, level } => {
-            lines.push(Line::from(vec![
-              Span::styled(
-                match level {
-                  1 => "  # ",
-                  2 => " ## ",
-                  3 => "### ",
-                  _ => unreachable!(),
-                },
-                Style::default().fg(Color::LightBlue),
-              ),
-              Span::styled(text, {
-                let mut style = Style::default().add_modifier(Modifier::BOLD);
-
-                match level {
-                  1 => {
-                    style = style.add_modifier(Modifier::UNDERLINED);
-                  }
-                  3 => {
-                    style = style.add_modifier(Modifier::ITALIC);
-                  }
-                  _ => {}
-                }
-
-                style
-              }),
-            ]));
-          }
-          germ::ast::Node::List(list_items) => {
-            let mut span_list = vec![];
-
-            for list_item in list_items {
-              span_list.push(Span::styled(
-                "  * ",
-                Style::default().fg(Color::LightBlue),
-              ));
-              span_list.push(Span::from(format!("{}\n", list_item)));
-            }
-
-            lines.push(Line::from(span_list));
-          }
-          germ::ast::Node::PreformattedText { text, alt_text } => {
-            let mut span_list = vec![
-              Span::styled("``` ", Style::default().fg(Color::LightBlue)),
-              Span::from(alt_text.unwrap_or_else(|| "".to_string())),
-            ];
-
-            if text != "sydney_abc_123" {
-              span_list.push(Span::from(text));
-            }
-
-            lines.push(Line::from(span_list));
-          }
-          germ::ast::Node::Whitespace => {
-            lines.push(Line::from("".to_string()));
-          }
-        };
+      if visual_range.is_some_and(|(start, end)| (start..=end).contains(&index))
+      {
+        item.style(Style::default().bg(palette.visual))
+      } else if app.changed.get(index) == Some(&true) {
+        item.style(Style::default().bg(palette.changed))
+      } else {
+        item
       }
-
-      ListItem::new(lines)
     })
     .collect();
 
+  app.item_heights = items.iter().map(ListItem::height).collect();
+
   let items = widgets::List::new(items)
     .highlight_style(
       Style::default()
-        .bg(Color::White)
-        .fg(Color::Black)
+        .bg(palette.selection_background)
+        .fg(palette.selection_foreground)
         .remove_modifier(Modifier::BOLD),
     )
-    .style(Style::default().bg(Color::Black).fg(Color::White));
+    .style(
+      Style::default()
+        .bg(palette.background)
+        .fg(palette.foreground),
+    );
+
+  app.list_area = if app.show_toc {
+    let page_chunks = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+      .split(page_area);
 
-  f.render_stateful_widget(items, chunks[0], &mut app.items.state);
+    toc(f, app, &palette, page_chunks[1]);
+
+    page_chunks[0]
+  } else {
+    page_area
+  };
+
+  f.render_stateful_widget(items, app.list_area, &mut app.items.state);
   f.render_widget(
-    Paragraph::new(app.url.to_string())
-      .style(Style::default().bg(Color::White).fg(Color::Black)),
+    Paragraph::new(if app.watch.is_some() {
+      format!("{} (watching)", app.url)
+    } else {
+      app.url.to_string()
+    })
+      .style(
+        Style::default()
+          .bg(palette.selection_background)
+          .fg(palette.selection_foreground),
+      ),
     chunks[1],
   );
 
   if let Some(error) = app.error.as_ref() {
     f.render_widget(
-      Paragraph::new(&**error).style(Style::default().bg(Color::Red)),
+      Paragraph::new(&**error).style(Style::default().bg(palette.error)),
       chunks[2],
     );
   } else if !app.input.is_empty()
     || app.input_mode == crate::input::Mode::Editing
   {
-    f.render_widget(Paragraph::new(format!(":{}", app.input)), chunks[2]);
+    f.render_widget(
+      Paragraph::new(format!(":{}", app.input.text())),
+      chunks[2],
+    );
+
+    if app.input_mode == crate::input::Mode::Editing {
+      #[allow(clippy::cast_possible_truncation)]
+      f.set_cursor(
+        chunks[2].x
+          + 1
+          + Span::raw(&app.input.text()[..app.input.cursor()]).width() as u16,
+        chunks[2].y,
+      );
+    }
+  } else if app.input_mode == crate::input::Mode::Visual {
+    f.render_widget(Paragraph::new("-- VISUAL LINE --"), chunks[2]);
+  } else if let Some(message) = &app.message {
+    f.render_widget(Paragraph::new(message.as_str()), chunks[2]);
   }
 
   if app.accept_response_input {
     let block = widgets::Block::default()
-      .title(app.url.to_string())
+      .title(app.upload.as_ref().unwrap_or(&app.url).to_string())
       .borders(widgets::Borders::ALL);
     let area = centered_rect(60, 20, f.size());
 
@@ -235,22 +168,314 @@ pub fn ui(f: &mut ratatui::Frame<'_>, app: &mut crate::App) {
     );
   }
 
+  if let Some(notification) = &app.notification {
+    let block = widgets::Block::default()
+      .title("Notification")
+      .borders(widgets::Borders::ALL)
+      .style(Style::default().bg(palette.background).fg(palette.accent));
+    let width = f.size().width.min(50);
+    let area = Rect::new(f.size().width - width, 0, width, 5);
+
+    f.render_widget(widgets::Clear, area);
+    f.render_widget(block.clone(), area);
+    f.render_widget(
+      Paragraph::new(notification.as_str())
+        .style(Style::default().fg(palette.foreground))
+        .wrap(widgets::Wrap { trim: false }),
+      block.inner(area),
+    );
+  }
+
   if let Some(error) = &app.error {
     let block = widgets::Block::default()
       .title("Sydney")
       .borders(widgets::Borders::ALL)
-      .style(Style::default().bg(Color::Cyan));
+      .style(Style::default().bg(palette.dialog));
     let area = centered_rect(60, 20, f.size());
 
     f.render_widget(widgets::Clear, area);
     f.render_widget(block.clone(), area);
     f.render_widget(
-      Paragraph::new(error.to_string()).wrap(widgets::Wrap { trim: false }),
+      Paragraph::new(error.clone()).wrap(widgets::Wrap { trim: false }),
       block.inner(area),
     );
   }
 }
 
+/// Render the items of a document into the text displayed for them, wrapping
+/// text at `wrap_at` columns
+#[must_use]
+pub fn render(
+  items: &[Item],
+  wrap_at: u16,
+  palette: &Palette,
+) -> Text<'static> {
+  Text::from(
+    items
+      .iter()
+      .flat_map(|item| render_item(item, wrap_at, palette))
+      .collect::<Vec<_>>(),
+  )
+}
+
+/// Render a document item into the lines displayed for it, wrapping text at
+/// `wrap_at` columns
+#[allow(clippy::too_many_lines)]
+pub fn render_item(
+  Item {
+    nodes: text_lines,
+    preformatted: pre,
+    prompt,
+    ..
+  }: &Item,
+  wrap_at: u16,
+  palette: &Palette,
+) -> Vec<Line<'static>> {
+  let width = usize::from(wrap_at).saturating_sub(5).max(1);
+  let mut lines = vec![];
+
+  for line in text_lines {
+    let mut line = line.clone();
+
+    if *pre {
+      if let Node::Text(text) = line {
+        line = Node::PreformattedText {
+          alt_text: None,
+          text: text.clone(),
+        }
+      }
+    }
+
+    macro_rules! wrap_split {
+      ($text:ident, $lines:ident) => {
+        let wrappeds = chunks(&$text, width);
+
+        for (i, wrapped) in wrappeds.iter().enumerate() {
+          $lines.push(Line::from(format!("    {}{}", wrapped, {
+            if i < wrappeds.len() - 1 && wrappeds.len() != 1 {
+              "-"
+            } else {
+              ""
+            }
+          })));
+        }
+      };
+    }
+
+    match line {
+      germ::ast::Node::Text(text) => {
+        if text != "sydney_abc_123" {
+          wrap_split!(text, lines);
+        }
+      }
+      germ::ast::Node::Blockquote(text) => {
+        let wrappeds = chunks(&text, width);
+
+        for (i, wrapped) in wrappeds.iter().enumerate() {
+          lines.push(Line::from(vec![
+            Span::styled("  > ", Style::default().fg(palette.accent)),
+            Span::styled(
+              format!("{}{}", wrapped.clone(), {
+                if i + 1 < wrappeds.len() {
+                  "-"
+                } else {
+                  ""
+                }
+              }),
+              Style::default().add_modifier(Modifier::ITALIC),
+            ),
+          ]));
+        }
+      }
+      germ::ast::Node::Link { to, text } => {
+        let mut span_list = vec![Span::styled(
+          if *prompt { " =: " } else { " => " },
+          Style::default().fg(palette.accent),
+        )];
+
+        span_list.push(Span::styled(
+          text.unwrap_or_else(|| to.clone()),
+          Style::default().add_modifier(Modifier::UNDERLINED),
+        ));
+        span_list.push(Span::from(" "));
+        span_list
+          .push(Span::styled(to, Style::default().fg(palette.accent)));
+
+        lines.push(Line::from(span_list));
+      }
+      // Documents which were not normalised may hold hashtags, which germ
+      // parses as headings of level zero, and headings deeper than three
+      germ::ast::Node::Heading { text, level: 0 } => {
+        wrap_split!(text, lines);
+      }
+      germ::ast::Node::Heading { text, level } => {
+        lines.push(Line::from(vec![
+          Span::styled(
+            match level {
+              1 => "  # ",
+              2 => " ## ",
+              _ => "### ",
+            },
+            Style::default().fg(palette.accent),
+          ),
+          Span::styled(text, {
+            let mut style = Style::default().add_modifier(Modifier::BOLD);
+
+            match level {
+              1 => {
+                style = style.add_modifier(Modifier::UNDERLINED);
+              }
+              3.. => {
+                style = style.add_modifier(Modifier::ITALIC);
+              }
+              _ => {}
+            }
+
+            style
+          }),
+        ]));
+      }
+      germ::ast::Node::List(list_items) => {
+        let mut span_list = vec![];
+
+        for list_item in list_items {
+          span_list.push(Span::styled(
+            "  * ",
+            Style::default().fg(palette.accent),
+          ));
+          span_list.push(Span::from(format!("{list_item}\n")));
+        }
+
+        lines.push(Line::from(span_list));
+      }
+      germ::ast::Node::PreformattedText { text, alt_text } => {
+        let mut span_list = vec![
+          Span::styled("``` ", Style::default().fg(palette.accent)),
+          Span::from(alt_text.unwrap_or_else(String::new)),
+        ];
+
+        if text != "sydney_abc_123" {
+          span_list.push(Span::from(text));
+        }
+
+        lines.push(Line::from(span_list));
+      }
+      germ::ast::Node::Whitespace => {
+        lines.push(Line::from(String::new()));
+      }
+    }
+  }
+
+  lines
+}
+
+/// Split `text` into chunks which each take up at most `width` columns, or a
+/// single character which is wider
+fn chunks(text: &str, width: usize) -> Vec<String> {
+  let mut chunks = vec![];
+  let mut chunk = String::new();
+  let mut columns = 0;
+
+  for character in text.chars() {
+    let character_width = character.width().unwrap_or(0);
+
+    if columns + character_width > width && !chunk.is_empty() {
+      chunks.push(std::mem::take(&mut chunk));
+
+      columns = 0;
+    }
+
+    chunk.push(character);
+
+    columns += character_width;
+  }
+
+  if !chunk.is_empty() {
+    chunks.push(chunk);
+  }
+
+  chunks
+}
+
+fn tabs(
+  f: &mut ratatui::Frame<'_>,
+  app: &crate::App,
+  palette: &Palette,
+  area: Rect,
+) {
+  let titles = app
+    .tabs
+    .iter()
+    .enumerate()
+    .map(|(index, tab)| {
+      crate::tab::title(if index == app.current_tab {
+        &app.url
+      } else {
+        &tab.url
+      })
+    })
+    .collect::<Vec<_>>();
+
+  f.render_widget(
+    widgets::Tabs::new(titles)
+      .select(app.current_tab)
+      .style(
+        Style::default()
+          .bg(palette.background)
+          .fg(palette.foreground),
+      )
+      .highlight_style(
+        Style::default()
+          .bg(palette.selection_background)
+          .fg(palette.selection_foreground)
+          .remove_modifier(Modifier::BOLD),
+      ),
+    area,
+  );
+}
+
+fn toc(
+  f: &mut ratatui::Frame<'_>,
+  app: &crate::App,
+  palette: &Palette,
+  area: Rect,
+) {
+  let headings = crate::toc::headings(&app.items.items);
+  let mut state = widgets::ListState::default();
+
+  if let Some(selected) = app.items.state.selected() {
+    state.select(crate::toc::current(&headings, selected));
+  }
+
+  let items = headings
+    .iter()
+    .map(|heading| {
+      ListItem::new(format!(
+        "{}{}",
+        "  ".repeat(heading.level.saturating_sub(1)),
+        heading.text
+      ))
+    })
+    .collect::<Vec<_>>();
+
+  f.render_stateful_widget(
+    widgets::List::new(items)
+      .block(
+        widgets::Block::default()
+          .title("Contents")
+          .borders(widgets::Borders::LEFT),
+      )
+      .highlight_style(Style::default().fg(palette.accent))
+      .style(
+        Style::default()
+          .bg(palette.background)
+          .fg(palette.foreground),
+      ),
+    area,
+    &mut state,
+  );
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
   let popup_layout = Layout::default()
     .direction(Direction::Vertical)
@@ -0,0 +1,209 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! The opt-in check for newer versions of Sydney
+
+use std::{
+  cmp::Ordering,
+  path::PathBuf,
+  sync::mpsc,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use url::Url;
+
+const VERSION_URL: &str = "gemini://fuwn.me/api/sydney/version";
+/// How long the latest version is remembered before it is requested again
+const CACHE_LIFETIME: Duration = Duration::from_hours(24);
+
+/// Check for a newer version of Sydney on a background thread
+///
+/// The returned channel receives a notification if a newer version is
+/// available. Failures are not reported, the check is simply tried again the
+/// next time Sydney starts.
+#[must_use]
+pub fn spawn() -> mpsc::Receiver<String> {
+  let (sender, receiver) = mpsc::channel();
+
+  std::thread::spawn(move || {
+    let Some(latest) = latest() else {
+      return;
+    };
+
+    if compare(&latest, env!("CARGO_PKG_VERSION")) == Some(Ordering::Greater) {
+      let _ = sender.send(format!(
+        "Sydney {latest} is available, you are using {}. Press Backspace to \
+         dismiss.",
+        env!("CARGO_PKG_VERSION")
+      ));
+    }
+  });
+
+  receiver
+}
+
+/// Compare two semantic versions, e.g., `0.1.9` and `0.1.11`
+///
+/// Versions with a pre-release, e.g., `0.2.0-rc.1`, are ordered before the
+/// release they precede.
+#[must_use]
+pub fn compare(left: &str, right: &str) -> Option<Ordering> {
+  let (left, left_pre) = parse(left)?;
+  let (right, right_pre) = parse(right)?;
+
+  Some(left.cmp(&right).then_with(|| {
+    match (left_pre, right_pre) {
+      (None, None) => Ordering::Equal,
+      (None, Some(_)) => Ordering::Greater,
+      (Some(_), None) => Ordering::Less,
+      (Some(left), Some(right)) => compare_pre_release(left, right),
+    }
+  }))
+}
+
+fn parse(version: &str) -> Option<([u64; 3], Option<&str>)> {
+  let version = version.trim().trim_start_matches('v');
+  let version = version
+    .split_once('+')
+    .map_or(version, |(version, _)| version);
+  let (release, pre) = version
+    .split_once('-')
+    .map_or((version, None), |(release, pre)| (release, Some(pre)));
+  let mut numbers = release.split('.').map(str::parse);
+  let parsed = [
+    numbers.next()?.ok()?,
+    numbers.next()?.ok()?,
+    numbers.next()?.ok()?,
+  ];
+
+  if numbers.next().is_some() {
+    return None;
+  }
+
+  Some((parsed, pre))
+}
+
+fn compare_pre_release(left: &str, right: &str) -> Ordering {
+  let mut left = left.split('.');
+  let mut right = right.split('.');
+
+  loop {
+    match (left.next(), right.next()) {
+      (None, None) => return Ordering::Equal,
+      (None, Some(_)) => return Ordering::Less,
+      (Some(_), None) => return Ordering::Greater,
+      (Some(left), Some(right)) => {
+        let ordering = match (left.parse::<u64>(), right.parse::<u64>()) {
+          (Ok(left), Ok(right)) => left.cmp(&right),
+          (Ok(_), Err(_)) => Ordering::Less,
+          (Err(_), Ok(_)) => Ordering::Greater,
+          (Err(_), Err(_)) => left.cmp(right),
+        };
+
+        if ordering != Ordering::Equal {
+          return ordering;
+        }
+      }
+    }
+  }
+}
+
+/// The latest version of Sydney, from the cache if it was requested recently
+fn latest() -> Option<String> {
+  if let Some(latest) = cached() {
+    return Some(latest);
+  }
+
+  let response =
+    crate::gemini::request(&Url::parse(VERSION_URL).ok()?, None).ok()?;
+
+  if !response.is_success() {
+    return None;
+  }
+
+  let latest = response.text().trim().to_string();
+
+  parse(&latest)?;
+
+  if let Some(path) = cache_path() {
+    if let Some(parent) = path.parent() {
+      let _ = std::fs::create_dir_all(parent);
+    }
+
+    let _ = std::fs::write(path, format!("{}\n{latest}\n", now()?.as_secs()));
+  }
+
+  Some(latest)
+}
+
+fn cached() -> Option<String> {
+  let content = std::fs::read_to_string(cache_path()?).ok()?;
+  let mut lines = content.lines();
+  let checked = Duration::from_secs(lines.next()?.parse().ok()?);
+
+  if now()?.saturating_sub(checked) > CACHE_LIFETIME {
+    return None;
+  }
+
+  lines.next().map(ToString::to_string)
+}
+
+fn cache_path() -> Option<PathBuf> {
+  crate::config::cache_directory().map(|directory| directory.join("version"))
+}
+
+fn now() -> Option<Duration> {
+  SystemTime::now().duration_since(UNIX_EPOCH).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn compares_versions() {
+    for (left, right, expected) in [
+      ("0.1.9", "0.1.11", Ordering::Less),
+      ("0.1.11", "0.1.11", Ordering::Equal),
+      ("1.0.0", "0.99.99", Ordering::Greater),
+      ("v0.2.0", "0.2.0", Ordering::Equal),
+      (" 0.2.0\n", "0.2.0", Ordering::Equal),
+      ("0.2.0+build.5", "0.2.0", Ordering::Equal),
+      ("0.2.0-rc.1", "0.2.0", Ordering::Less),
+      ("0.2.0-rc.1", "0.1.11", Ordering::Greater),
+      ("0.2.0-alpha", "0.2.0-alpha.1", Ordering::Less),
+      ("0.2.0-alpha.1", "0.2.0-alpha.beta", Ordering::Less),
+      ("0.2.0-alpha.beta", "0.2.0-beta", Ordering::Less),
+      ("0.2.0-beta.2", "0.2.0-beta.11", Ordering::Less),
+      ("0.2.0-rc.1", "0.2.0-rc.1+build", Ordering::Equal),
+    ] {
+      assert_eq!(compare(left, right), Some(expected), "{left} {right}");
+      assert_eq!(
+        compare(right, left),
+        Some(expected.reverse()),
+        "{right} {left}"
+      );
+    }
+  }
+
+  #[test]
+  fn rejects_invalid_versions() {
+    for version in ["", "0.2", "0.2.0.1", "0.x.0", "-1.0.0", "latest"] {
+      assert_eq!(compare(version, "0.2.0"), None, "{version:?}");
+    }
+  }
+}
@@ -15,10 +15,151 @@
 // Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
 // SPDX-License-Identifier: GPL-3.0-only
 
+//! Resolving links and locations given on the command line
+
+use std::fmt::Write;
+
+use url::Url;
+
+/// Resolve a link found on the page at `base`, yielding `None` for links to
+/// schemes Sydney does not support
+///
+/// Links to local files are only followed from other local files, so that a
+/// capsule cannot point Sydney at the local file system.
+///
+/// # Errors
+///
+/// If `link` is not a valid URL relative to `base`.
+pub fn resolve(base: &Url, link: &str) -> Result<Option<Url>, String> {
+  let url = base
+    .join(link)
+    .map_err(|error| format!("Could not resolve {link}: {error}"))?;
+
+  Ok(match url.scheme() {
+    "gemini" | "gopher" | "guppy" | "spartan" | "finger" | "nex" | "misfin"
+    | "about" => Some(url),
+    "file" if base.scheme() == "file" => Some(url),
+    _ => None,
+  })
+}
+
+/// Parse a URL, or a path to a local file if it starts with `/`, `.` or `~`
+/// or names a file which exists
+///
+/// # Errors
+///
+/// If `location` is neither a valid URL nor a valid path.
+pub fn parse_location(location: &str) -> Result<Url, String> {
+  if location.starts_with('/')
+    || location.starts_with('.')
+    || location.starts_with('~')
+    || (!location.contains("://")
+      && !location.starts_with("about:")
+      && std::path::Path::new(location).exists())
+  {
+    let path = expand_path(location)?;
+
+    return if path.is_dir() {
+      Url::from_directory_path(&path)
+    } else {
+      Url::from_file_path(&path)
+    }
+    .map_err(|()| format!("{} is not a valid path", path.display()));
+  }
+
+  Url::parse(&prefix_gemini(location)).map_err(|error| error.to_string())
+}
+
+/// Make `path` absolute, expanding a leading `~` to the home directory
+///
+/// # Errors
+///
+/// If the home directory or the current directory could not be found.
+pub fn expand_path(path: &str) -> Result<std::path::PathBuf, String> {
+  let path = match path.strip_prefix('~') {
+    Some(rest) =>
+      crate::config::home()
+        .ok_or_else(|| "Could not locate the home directory".to_string())?
+        .join(rest.trim_start_matches('/')),
+    None => std::path::PathBuf::from(path),
+  };
+
+  std::path::absolute(&path).map_err(|error| error.to_string())
+}
+
+/// Decode the `%XX` escapes of a URL component, leaving invalid escapes as
+/// they are
+#[must_use]
+pub fn percent_decode(component: &str) -> Vec<u8> {
+  let bytes = component.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let escaped = (bytes[i] == b'%')
+      .then(|| bytes.get(i + 1..i + 3))
+      .flatten()
+      .and_then(|hex| std::str::from_utf8(hex).ok())
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+    if let Some(byte) = escaped {
+      decoded.push(byte);
+
+      i += 3;
+    } else {
+      decoded.push(bytes[i]);
+
+      i += 1;
+    }
+  }
+
+  decoded
+}
+
+/// Percent-encode `text` for use as a URL component, escaping everything but
+/// unreserved characters so that it is decoded to exactly `text`
+#[must_use]
+pub fn percent_encode(text: &str) -> String {
+  let mut encoded = String::with_capacity(text.len());
+
+  for byte in text.bytes() {
+    if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+      encoded.push(char::from(byte));
+    } else {
+      let _ = write!(encoded, "%{byte:02X}");
+    }
+  }
+
+  encoded
+}
+
+/// Prefix `url` with `gemini://` if it has no scheme
+#[must_use]
 pub fn prefix_gemini(url: &str) -> String {
-  if url.starts_with("gemini://") {
+  if url.contains("://") || url.starts_with("about:") {
     url.to_string()
   } else {
-    format!("gemini://{}", url)
+    format!("gemini://{url}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn percent_encodes_what_it_decodes() {
+    for (text, encoded) in [
+      ("", ""),
+      ("gemini capsules", "gemini%20capsules"),
+      ("a#b?c&d=e", "a%23b%3Fc%26d%3De"),
+      ("100%41", "100%2541"),
+      ("line\r\nbreak", "line%0D%0Abreak"),
+      ("日本", "%E6%97%A5%E6%9C%AC"),
+      ("-._~", "-._~"),
+    ] {
+      assert_eq!(percent_encode(text), encoded, "{text:?}");
+      assert_eq!(percent_decode(encoded), text.as_bytes(), "{encoded:?}");
+    }
   }
 }
@@ -0,0 +1,202 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Reloading the current page as it changes, see `:watch`
+
+use std::time::{Duration, Instant, SystemTime};
+
+use url::Url;
+
+/// Lines are only compared one by one beyond this many pairs, to keep
+/// reloads of huge pages fast
+const DIFF_LIMIT: usize = 4_000_000;
+
+pub struct Watch {
+  /// The page being watched, watching stops once it is navigated away from
+  pub url: Url,
+  trigger: Trigger,
+}
+
+enum Trigger {
+  Interval {
+    every: Duration,
+    last:  Instant,
+  },
+  /// Reload whenever the modification time of a local file changes
+  Modified(Option<SystemTime>),
+}
+
+impl Watch {
+  /// Watch `url`, every `every` if given, otherwise whenever the file behind
+  /// a `file://` URL changes
+  pub fn new(url: Url, every: Option<Duration>) -> Result<Self, String> {
+    let trigger = match every {
+      Some(every) =>
+        Trigger::Interval {
+          every,
+          last: Instant::now(),
+        },
+      None if url.scheme() == "file" => Trigger::Modified(modified(&url)),
+      None =>
+        return Err(
+          "Only local files can be watched for changes, give an interval in \
+           seconds instead"
+            .to_string(),
+        ),
+    };
+
+    Ok(Self {
+      url,
+      trigger,
+    })
+  }
+
+  pub fn describe(&self) -> String {
+    match &self.trigger {
+      Trigger::Interval {
+        every, ..
+      } => format!("Reloading {} every {}s", self.url, every.as_secs()),
+      Trigger::Modified(_) =>
+        format!("Reloading {} whenever it changes", self.url),
+    }
+  }
+
+  /// Whether the page should be reloaded now, restarting the wait if so
+  pub fn due(&mut self) -> bool {
+    match &mut self.trigger {
+      Trigger::Interval {
+        every,
+        last,
+      } => {
+        if last.elapsed() < *every {
+          return false;
+        }
+
+        *last = Instant::now();
+
+        true
+      }
+      Trigger::Modified(last) => {
+        let modified = modified(&self.url);
+
+        if modified == *last {
+          return false;
+        }
+
+        *last = modified;
+
+        true
+      }
+    }
+  }
+}
+
+fn modified(url: &Url) -> Option<SystemTime> {
+  std::fs::metadata(url.to_file_path().ok()?)
+    .ok()?
+    .modified()
+    .ok()
+}
+
+/// Which lines of `new` are not in `old`, by their longest common subsequence
+pub fn changed_lines(old: &str, new: &str) -> Vec<bool> {
+  let old = old.lines().collect::<Vec<_>>();
+  let new = new.lines().collect::<Vec<_>>();
+  let prefix = old
+    .iter()
+    .zip(&new)
+    .take_while(|(old, new)| old == new)
+    .count();
+  let suffix = old[prefix..]
+    .iter()
+    .rev()
+    .zip(new[prefix..].iter().rev())
+    .take_while(|(old, new)| old == new)
+    .count();
+  let old_middle = &old[prefix..old.len() - suffix];
+  let new_middle = &new[prefix..new.len() - suffix];
+  let mut changed = vec![false; new.len()];
+
+  if old_middle.len().saturating_mul(new_middle.len()) > DIFF_LIMIT {
+    changed[prefix..new.len() - suffix].fill(true);
+
+    return changed;
+  }
+
+  // lengths[i][j] is the length of the longest common subsequence of
+  // old_middle[i..] and new_middle[j..]
+  let mut lengths =
+    vec![vec![0usize; new_middle.len() + 1]; old_middle.len() + 1];
+
+  for i in (0..old_middle.len()).rev() {
+    for j in (0..new_middle.len()).rev() {
+      lengths[i][j] = if old_middle[i] == new_middle[j] {
+        lengths[i + 1][j + 1] + 1
+      } else {
+        lengths[i + 1][j].max(lengths[i][j + 1])
+      };
+    }
+  }
+
+  let (mut i, mut j) = (0, 0);
+
+  while j < new_middle.len() {
+    if i < old_middle.len() && old_middle[i] == new_middle[j] {
+      i += 1;
+      j += 1;
+    } else if i < old_middle.len() && lengths[i + 1][j] >= lengths[i][j + 1] {
+      i += 1;
+    } else {
+      changed[prefix + j] = true;
+      j += 1;
+    }
+  }
+
+  changed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn marks_changed_lines() {
+    for (old, new, expected) in [
+      ("a\nb\nc", "a\nb\nc", vec![false, false, false]),
+      ("a\nc", "a\nb\nc", vec![false, true, false]),
+      ("a\nb\nc", "a\nc", vec![false, false]),
+      ("a\nb\nc", "a\nB\nc", vec![false, true, false]),
+      ("a\nb\nc", "x\na\nc\ny", vec![true, false, false, true]),
+      ("", "a\nb", vec![true, true]),
+      ("a\nb", "", vec![]),
+    ] {
+      assert_eq!(changed_lines(old, new), expected, "{old:?} -> {new:?}");
+    }
+  }
+
+  #[test]
+  fn marks_everything_between_unchanged_ends_beyond_the_limit() {
+    let lines = (0..2_001).map(|i| i.to_string()).collect::<Vec<_>>();
+    let old = format!("first\n{}\nlast", lines.join("\n"));
+    let new = format!("first\nx\n{}\ny\nlast", lines[..2_000].join("\n"));
+    let changed = changed_lines(&old, &new);
+
+    assert_eq!(changed.len(), 2_004);
+    assert!(!changed[0] && !changed[2_003]);
+    assert!(changed[1..2_003].iter().all(|&changed| changed));
+  }
+}
@@ -0,0 +1,147 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+//! Browsing capsules served from loopback like a user would
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{open, press, render, route, slow, text, Server};
+use sydney::App;
+
+const HOME: &str =
+  "20 text/gemini\r\n# Home\n=> /about About\n=> /search Search\n";
+
+fn rendered(app: &mut App) -> String { text(&render(app, 80, 24)).join("\n") }
+
+#[test]
+fn follows_links_and_moves_through_history() {
+  let server = Server::start(vec![
+    route("/", HOME),
+    route("/about", "20 text/gemini\r\n# About\n"),
+  ]);
+  let mut app = open(server.url("/"));
+
+  assert!(rendered(&mut app).contains("# Home"));
+
+  press(&mut app, "jj\n");
+
+  assert_eq!(app.url(), &server.url("/about"));
+  assert!(rendered(&mut app).contains("# About"));
+
+  press(&mut app, "h");
+
+  assert_eq!(app.url(), &server.url("/"));
+
+  press(&mut app, "l");
+
+  assert_eq!(app.url(), &server.url("/about"));
+  assert_eq!(server.requests(), ["/", "/about", "/", "/about"]);
+}
+
+#[test]
+fn follows_redirects() {
+  let server = Server::start(vec![
+    route("/old", "31 /new\r\n"),
+    route("/new", "20 text/gemini\r\n# New\n"),
+  ]);
+  let mut app = open(server.url("/old"));
+
+  assert_eq!(app.url(), &server.url("/new"));
+  assert_eq!(app.history(), [server.url("/new")]);
+  assert!(rendered(&mut app).contains(server.url("/new").as_str()));
+}
+
+#[test]
+fn sends_input() {
+  let server = Server::start(vec![
+    route("/search", "10 What are you looking for?\r\n"),
+    route("/search?gemini%20capsules", "20 text/gemini\r\n# Results\n"),
+  ]);
+  let mut app = open(server.url("/search"));
+
+  assert!(app.accepts_input());
+  assert!(rendered(&mut app).contains("What are you looking for?"));
+
+  press(&mut app, "gemini capsules\n");
+
+  assert!(!app.accepts_input());
+  assert_eq!(app.url(), &server.url("/search?gemini%20capsules"));
+  assert_eq!(app.source(), "# Results\n");
+}
+
+#[test]
+fn sends_input_as_typed() {
+  let server = Server::start(vec![
+    route("/search", "10 What are you looking for?\r\n"),
+    route("/search?100%25%20%2341%3F", "20 text/gemini\r\n# Results\n"),
+  ]);
+  let mut app = open(server.url("/search"));
+
+  press(&mut app, "100% #41?\n");
+
+  assert_eq!(server.requests(), ["/search", "/search?100%25%20%2341%3F"]);
+  assert_eq!(app.source(), "# Results\n");
+}
+
+#[test]
+fn shows_errors() {
+  let server = Server::start(vec![route("/gone", "52 Gone for good\r\n")]);
+  let mut app = open(server.url("/gone"));
+
+  assert_eq!(app.error(), Some("Gone for good"));
+  assert!(rendered(&mut app).contains("Gone for good"));
+
+  let app = open(server.url("/missing"));
+
+  assert_eq!(app.error(), Some("Not found"));
+
+  let app = open(url::Url::parse("gemini://127.0.0.1:1/").unwrap());
+
+  assert!(app
+    .error()
+    .is_some_and(|error| error.starts_with("Could not connect")));
+}
+
+#[test]
+fn waits_for_slow_responses() {
+  let delay = Duration::from_millis(300);
+  let server =
+    Server::start(vec![slow("/slow", "20 text/gemini\r\n# Finally\n", delay)]);
+  let start = Instant::now();
+  let app = open(server.url("/slow"));
+
+  assert!(start.elapsed() >= delay);
+  assert_eq!(app.source(), "# Finally\n");
+}
+
+/// Certificates are not pinned yet, as trust on first use is not implemented,
+/// so a capsule presenting a different certificate goes unnoticed. Once it
+/// is, this should expect the change to be reported instead.
+#[test]
+fn does_not_pin_certificates_yet() {
+  let server = Server::start(vec![route("/", HOME)]);
+  let mut app = open(server.url("/"));
+
+  server.change_certificate();
+
+  press(&mut app, "r");
+
+  assert_eq!(app.error(), None);
+  assert_eq!(server.requests(), ["/", "/"]);
+  assert!(rendered(&mut app).contains("# Home"));
+}
@@ -0,0 +1,219 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+//! A Gemini server on loopback answering with scripted responses, and helpers
+//! to drive `App` like a user would
+//!
+//! Each test crate uses only some of the helpers.
+
+#![allow(dead_code)]
+
+use std::{
+  io::{Read, Write},
+  net::TcpListener,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use rustls::{
+  Certificate,
+  PrivateKey,
+  ServerConfig,
+  ServerConnection,
+  StreamOwned,
+};
+use sydney::{config::Config, input, ui, App};
+use url::Url;
+
+/// A scripted response to requests for a path, query included
+pub struct Route {
+  pub path:     &'static str,
+  /// The raw response, header included
+  pub response: &'static str,
+  /// How long to wait before responding
+  pub delay:    Duration,
+}
+
+pub const fn route(path: &'static str, response: &'static str) -> Route {
+  Route {
+    path,
+    response,
+    delay: Duration::ZERO,
+  }
+}
+
+pub const fn slow(
+  path: &'static str,
+  response: &'static str,
+  delay: Duration,
+) -> Route {
+  Route {
+    path,
+    response,
+    delay,
+  }
+}
+
+pub struct Server {
+  port:     u16,
+  tls:      Arc<Mutex<Arc<ServerConfig>>>,
+  /// The path of every request received, in order
+  requests: Arc<Mutex<Vec<String>>>,
+}
+impl Server {
+  /// Serve `routes` until the tests finish, answering requests for any other
+  /// path with `51 Not found`
+  pub fn start(routes: Vec<Route>) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = Self {
+      port:     listener.local_addr().unwrap().port(),
+      tls:      Arc::new(Mutex::new(tls())),
+      requests: Arc::default(),
+    };
+    let routes = Arc::new(routes);
+    let tls = server.tls.clone();
+    let requests = server.requests.clone();
+
+    std::thread::spawn(move || {
+      for stream in listener.incoming().map_while(Result::ok) {
+        let tls = tls.lock().unwrap().clone();
+        let routes = routes.clone();
+        let requests = requests.clone();
+
+        std::thread::spawn(move || {
+          let connection = ServerConnection::new(tls).unwrap();
+          let mut stream = StreamOwned::new(connection, stream);
+          let mut request = vec![];
+          let mut byte = [0];
+
+          while !request.ends_with(b"\r\n") {
+            if stream.read(&mut byte).unwrap_or(0) == 0 {
+              return;
+            }
+
+            request.push(byte[0]);
+          }
+
+          let url =
+            Url::parse(String::from_utf8_lossy(&request).trim()).unwrap();
+          let path = format!(
+            "{}{}",
+            url.path(),
+            url
+              .query()
+              .map(|query| format!("?{query}"))
+              .unwrap_or_default()
+          );
+          let route = routes.iter().find(|route| route.path == path);
+
+          requests.lock().unwrap().push(path);
+
+          if let Some(route) = route {
+            std::thread::sleep(route.delay);
+          }
+
+          let _ =
+            stream
+              .write_all(route.map_or(b"51 Not found\r\n", |route| {
+                route.response.as_bytes()
+              }));
+
+          stream.conn.send_close_notify();
+
+          let _ = stream.flush();
+        });
+      }
+    });
+
+    server
+  }
+
+  pub fn url(&self, path: &str) -> Url {
+    Url::parse(&format!("gemini://127.0.0.1:{}{path}", self.port)).unwrap()
+  }
+
+  pub fn requests(&self) -> Vec<String> {
+    self.requests.lock().unwrap().clone()
+  }
+
+  /// Present a newly generated certificate from now on
+  pub fn change_certificate(&self) { *self.tls.lock().unwrap() = tls(); }
+}
+
+fn tls() -> Arc<ServerConfig> {
+  let certificate =
+    rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+
+  Arc::new(
+    ServerConfig::builder()
+      .with_safe_defaults()
+      .with_no_client_auth()
+      .with_single_cert(
+        vec![Certificate(certificate.serialize_der().unwrap())],
+        PrivateKey(certificate.serialize_private_key_der()),
+      )
+      .unwrap(),
+  )
+}
+
+/// An `App` with the default configuration showing `url`
+pub fn open(url: Url) -> App {
+  let mut app = App::new(Config::default());
+
+  app.set_wrap_at(80);
+  app.set_url(url);
+
+  app.make_request();
+
+  app
+}
+
+/// Press each key of `keys` in turn, where `\n` is Enter and `\x1b` Escape
+pub fn press(app: &mut App, keys: &str) {
+  for character in keys.chars() {
+    let code = match character {
+      '\n' => KeyCode::Enter,
+      '\x1b' => KeyCode::Esc,
+      character => KeyCode::Char(character),
+    };
+
+    input::handle_key_strokes(app, KeyEvent::new(code, KeyModifiers::NONE));
+  }
+}
+
+/// Draw `app` on a terminal of the given size
+pub fn render(app: &mut App, width: u16, height: u16) -> Buffer {
+  let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+
+  terminal.draw(|f| ui::ui(f, app)).unwrap();
+
+  terminal.backend().buffer().clone()
+}
+
+/// The text of every row of `buffer`, without trailing whitespace
+pub fn text(buffer: &Buffer) -> Vec<String> {
+  (0..buffer.area.height)
+    .map(|y| {
+      (0..buffer.area.width)
+        .map(|x| buffer.get(x, y).symbol())
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+    })
+    .collect()
+}
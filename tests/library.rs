@@ -0,0 +1,137 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+
+//! Sydney used as a library, through its public API alone
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use sydney::{
+  config::Config,
+  document,
+  input,
+  request::Response,
+  transport::{Fake, Registry},
+  ui,
+  url::resolve,
+  App,
+};
+use url::Url;
+
+const HOME: &str = "# Home\n=> next Next\n=> https://example.com Web\n";
+
+fn registry() -> Registry {
+  let mut registry = Registry::empty();
+
+  registry.register(
+    "gemini",
+    Fake::default()
+      .serve("gemini://capsule/", Response::success("text/gemini", HOME))
+      .serve(
+        "gemini://capsule/next",
+        Response::success("text/plain", "Next"),
+      ),
+  );
+
+  registry
+}
+
+#[test]
+fn renders_documents() {
+  let items = document::parse(HOME);
+  let text = ui::render(&items, 80, &Config::default().theme.palette());
+
+  assert_eq!(items.len(), 3);
+  assert_eq!(items[1].link.as_deref(), Some("next"));
+  assert_eq!(text.lines.len(), 3);
+  assert!(text.lines[0]
+    .spans
+    .iter()
+    .any(|span| span.content == "Home"));
+}
+
+#[test]
+fn resolves_links() {
+  let base = Url::parse("gemini://capsule/docs/").unwrap();
+
+  assert_eq!(
+    resolve(&base, "../about").unwrap().unwrap().as_str(),
+    "gemini://capsule/about"
+  );
+  assert_eq!(resolve(&base, "https://example.com").unwrap(), None);
+}
+
+#[test]
+fn fetches_through_registered_transports() {
+  let (url, response) = registry()
+    .fetch(
+      &Url::parse("gemini://capsule/").unwrap(),
+      &Config::default(),
+    )
+    .unwrap();
+
+  assert_eq!(url.as_str(), "gemini://capsule/");
+  assert!(response.is_success());
+  assert_eq!(response.text(), HOME);
+}
+
+#[test]
+fn drives_the_app() {
+  let mut app = App::new(Config::default());
+
+  app.set_transports(registry());
+  app.set_url(Url::parse("gemini://capsule/").unwrap());
+
+  app.make_request();
+
+  for code in [KeyCode::Char('j'), KeyCode::Char('j'), KeyCode::Enter] {
+    input::handle_key_strokes(
+      &mut app,
+      KeyEvent::new(code, KeyModifiers::NONE),
+    );
+  }
+
+  assert_eq!(app.url().as_str(), "gemini://capsule/next");
+  assert_eq!(app.source(), "Next");
+
+  app.go_back();
+
+  assert_eq!(app.url().as_str(), "gemini://capsule/");
+}
+
+#[test]
+fn moves_around_empty_pages() {
+  let mut app = App::new(Config::default());
+
+  app.make_request();
+  app.follow_item(0);
+
+  for code in [
+    KeyCode::Char('j'),
+    KeyCode::Char('k'),
+    KeyCode::Char('G'),
+    KeyCode::Char('g'),
+    KeyCode::Char('g'),
+    KeyCode::Enter,
+  ] {
+    input::handle_key_strokes(
+      &mut app,
+      KeyEvent::new(code, KeyModifiers::NONE),
+    );
+  }
+
+  assert_eq!(app.url().as_str(), "about:blank");
+  assert!(app.items().is_empty());
+}
@@ -0,0 +1,123 @@
+// This file is part of Sydney <https://github.com/gemrest/sydney>.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, version 3.
+//
+// This program is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+//
+// Copyright (C) 2022-2022 Fuwn <contact@fuwn.me>
+// SPDX-License-Identifier: GPL-3.0-only
+//! Snapshots of documents from `tests/fixtures` rendered at several widths,
+//! stored in `tests/snapshots`
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS` set to accept changes to how
+//! documents are rendered.
+
+mod common;
+
+use std::{fmt::Write, path::Path};
+
+use ratatui::{buffer::Buffer, style::Modifier};
+use sydney::{config::Config, theme::Theme, App};
+
+const WIDTHS: [u16; 3] = [20, 40, 80];
+const HEIGHT: u16 = 30;
+
+/// The text of every row of `buffer`, followed by each run of cells which
+/// is not in the default style of the theme
+fn snapshot(buffer: &Buffer) -> String {
+  let palette = Theme::Dark.palette();
+  let default = (palette.foreground, palette.background, Modifier::empty());
+  let mut snapshot = common::text(buffer).join("\n");
+
+  snapshot.push_str("\n\n");
+
+  for y in 0..buffer.area.height {
+    let mut x = 0;
+
+    while x < buffer.area.width {
+      let cell = buffer.get(x, y);
+      let style = (cell.fg, cell.bg, cell.modifier);
+      let start = x;
+
+      while x < buffer.area.width && {
+        let cell = buffer.get(x, y);
+
+        (cell.fg, cell.bg, cell.modifier) == style
+      } {
+        x += 1;
+      }
+
+      if style != default {
+        let _ = writeln!(
+          snapshot,
+          "{y}:{start}..{x} fg={:?} bg={:?} modifier={:?}",
+          style.0, style.1, style.2
+        );
+      }
+    }
+  }
+
+  snapshot
+}
+
+fn check(fixture: &str) {
+  let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+  let source =
+    std::fs::read_to_string(directory.join(format!("fixtures/{fixture}.gmi")))
+      .unwrap();
+
+  for width in WIDTHS {
+    let mut app = App::new(Config::default());
+
+    app.set_wrap_at(width);
+    app.set_url(url::Url::parse(sydney::stdin::URL).unwrap());
+    app.set_stdin(source.clone(), None);
+
+    app.make_request();
+
+    let actual = snapshot(&common::render(&mut app, width, HEIGHT));
+    let path = directory.join(format!("snapshots/{fixture}.{width}.snap"));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+      std::fs::write(&path, &actual).unwrap();
+    } else {
+      let expected = std::fs::read_to_string(&path).unwrap_or_default();
+
+      assert!(
+        expected == actual,
+        "{fixture} at {width} columns differs from {}, set UPDATE_SNAPSHOTS \
+         to accept the change\n\nexpected:\n{expected}\nactual:\n{actual}",
+        path.display()
+      );
+    }
+  }
+}
+
+#[test]
+fn empty() { check("empty"); }
+
+#[test]
+fn unicode() { check("unicode"); }
+
+#[test]
+fn long_lines() { check("long_lines"); }
+
+#[test]
+fn quotes() { check("quotes"); }
+
+#[test]
+fn preformatted() { check("preformatted"); }
+
+#[test]
+fn mixed() { check("mixed"); }
+
+#[test]
+fn headings() { check("headings"); }